@@ -2,11 +2,13 @@ use ark_relations::{
     lc,
     r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
 };
-use colored::Colorize;
 use ark_std::rand::RngCore;
+use std::io;
 
-use super::circuit_traits::BenchCircuit;
-use ark_ff::Field;
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{padding, print_stats, surplus_padding, BenchCircuit, ParamSpec};
+use super::witness::Witness;
+use ark_ff::{Field, PrimeField};
 
 #[derive(Copy, Clone)]
 pub struct AdditionCircuit<F: Field> {
@@ -15,28 +17,94 @@ pub struct AdditionCircuit<F: Field> {
     s: Option<F>,
     num_constraints: usize,
     num_variables: usize,
+    constrain_padding: bool,
     print: bool,
     
 }
 
-impl<F: Field> BenchCircuit<F> for AdditionCircuit<F> {
-    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, printing: bool) -> Self {
-        AdditionCircuit { 
-            a: Some(<F>::rand(rng)),  
-            b: Some(<F>::rand(rng)),  
-            s: Some(<F>::rand(rng)),  
-            num_constraints: if constraints < 4 { 4 } else { constraints },
-            num_variables: if constraints < 4 { 4 } else { constraints },
+impl<F: Field> AdditionCircuit<F> {
+    pub fn new(a: F, b: F, s: F, constraints: usize, variables: usize, constrain_padding: bool, printing: bool) -> Self {
+        AdditionCircuit { a: Some(a), b: Some(b), s: Some(s), ..Self::blank(constraints, variables, constrain_padding, printing) }
+    }
+
+    /// Circuit of the same shape as `new` with no witness.
+    pub fn blank(constraints: usize, variables: usize, constrain_padding: bool, printing: bool) -> Self {
+        AdditionCircuit {
+            a: None,
+            b: None,
+            s: None,
+            num_constraints: constraints.max(4),
+            num_variables: variables.max(4),
+            constrain_padding,
             print: printing,
         }
     }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for AdditionCircuit<F> {
+    fn name() -> &'static str {
+        "addition"
+    }
 
-    fn get_result(&self) -> F {
-        if let (Some(a_val), Some(b_val), Some(s_val)) = (self.a, self.b, self.s) {
-            return (a_val + s_val) * b_val;
-        } else {
-            return <F>::zero();
-        }
+    fn description() -> &'static str {
+        "Repeats the constraint (a + s) * b = c"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "variables", default: "constraints", description: "Number of variables, independent of --constraints" },
+            ParamSpec { key: "constrain_padding", default: "false", description: "Tie the padding variables to the existing constraints" },
+        ]
+    }
+
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::new(
+            <F>::rand(rng),
+            <F>::rand(rng),
+            <F>::rand(rng),
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        )
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        )
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        Ok(Self::new(
+            witness.field("a")?,
+            witness.field("b")?,
+            witness.field("s")?,
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        ))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("a", [self.a]);
+        witness.set_fields("b", [self.b]);
+        witness.set_fields("s", [self.s]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        AdditionCircuit { a: None, b: None, s: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        let surplus = surplus_padding(self.num_variables - 4, self.num_constraints, self.constrain_padding);
+        vec![("constraints", self.num_constraints + surplus), ("variables", self.num_variables)]
     }
 } 
 
@@ -56,40 +124,13 @@ impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for AdditionCircuit<
             }
         })?;
 
-        for _ in 0..(self.num_variables - 4) { 
-            let _ = cs.new_witness_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
-        }
+        let padding = padding(&cs, self.num_variables - 4, (a, self.a), self.num_constraints, self.constrain_padding)?;
 
-        for _ in 0..(self.num_constraints) {
-            cs.enforce_constraint(lc!() + a + s, lc!() + b, lc!() + c)?;
+        for term in &padding {
+            cs.enforce_constraint(lc!() + a + s, lc!() + b, lc!() + c + term)?;
         }
         if self.print == true{
-            println!("{} {}", Colorize::green("Constraints:"), cs.num_constraints());
-            println!("{} {}", Colorize::green("Variables:"), cs.num_constraints());
-            let matrices = cs.to_matrices().unwrap();
-            println!("{} {}", Colorize::green("Num witness variables:"), cs.num_witness_variables() + 1);
-            println!(
-                "{} A: {}, B: {}, C: {}", 
-                Colorize::blue("R1CS non-zeros -"),
-                matrices.a_num_non_zero,
-                matrices.b_num_non_zero,
-                matrices.c_num_non_zero,
-            );
-            let matrix_num_values = cs.num_constraints() * (cs.num_witness_variables() + 1);
-            println!(
-                "{} A: {}, B: {}, C: {}", 
-                Colorize::blue("R1CS zeros -"),
-                matrix_num_values - matrices.a_num_non_zero,
-                matrix_num_values - matrices.b_num_non_zero,
-                matrix_num_values - matrices.c_num_non_zero,
-            );
-            println!(
-                "{} A: {}%, B: {}%, C: {}%", 
-                Colorize::blue("R1CS sparsity -"),
-                ((matrix_num_values - matrices.a_num_non_zero) * 100) / matrix_num_values,
-                (matrix_num_values - matrices.b_num_non_zero) * 100 / matrix_num_values,
-                (matrix_num_values - matrices.c_num_non_zero) * 100 / matrix_num_values,
-            );
+            print_stats(&cs);
         }
         Ok(())
     }
@@ -0,0 +1,342 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use ark_ff::{Field, PrimeField};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{or_exit, print_stats, BenchCircuit, ParamSpec};
+use super::witness::{expect_len, invalid, Witness};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Gate {
+    And(usize, usize, usize),
+    Xor(usize, usize, usize),
+    Inv(usize, usize),
+    Eqw(usize, usize),
+    Eq(bool, usize),
+}
+
+/// Boolean circuit in Bristol Fashion. The first `num_inputs()` wires are the
+/// inputs and the last `num_outputs()` wires are the outputs.
+pub struct Bristol {
+    pub num_wires: usize,
+    pub input_sizes: Vec<usize>,
+    pub output_sizes: Vec<usize>,
+    pub gates: Vec<Gate>,
+}
+
+impl Bristol {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        let mut next_numbers = |what: &str| -> io::Result<Vec<usize>> {
+            let line = lines.next().ok_or_else(|| invalid(format!("missing {} line", what)))?;
+            line.split_whitespace().map(parse_usize).collect()
+        };
+        let header = next_numbers("header")?;
+        let inputs = next_numbers("input")?;
+        let outputs = next_numbers("output")?;
+        if header.len() != 2 || inputs.is_empty() || outputs.is_empty() {
+            return Err(invalid("malformed Bristol Fashion header"));
+        }
+        let (num_gates, num_wires) = (header[0], header[1]);
+        let input_sizes = inputs[1..].to_vec();
+        let output_sizes = outputs[1..].to_vec();
+        if input_sizes.len() != inputs[0] || output_sizes.len() != outputs[0] {
+            return Err(invalid("malformed Bristol Fashion header"));
+        }
+
+        let wire = |token: &str| -> io::Result<usize> {
+            let wire = parse_usize(token)?;
+            if wire < num_wires { Ok(wire) } else { Err(invalid(format!("wire {} out of range", wire))) }
+        };
+        let mut gates = Vec::new();
+        let mut num_gate_lines = 0;
+        for line in lines {
+            num_gate_lines += 1;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let (kind, operands) = tokens.split_last().unwrap();
+            match (*kind, operands) {
+                ("AND", [_, _, a, b, c]) => gates.push(Gate::And(wire(a)?, wire(b)?, wire(c)?)),
+                ("XOR", [_, _, a, b, c]) => gates.push(Gate::Xor(wire(a)?, wire(b)?, wire(c)?)),
+                ("INV", [_, _, a, c]) => gates.push(Gate::Inv(wire(a)?, wire(c)?)),
+                ("EQW", [_, _, a, c]) => gates.push(Gate::Eqw(wire(a)?, wire(c)?)),
+                ("EQ", [_, _, value, c]) => {
+                    let value = match *value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(invalid(format!("EQ constant must be 0 or 1 in `{}`", line))),
+                    };
+                    gates.push(Gate::Eq(value, wire(c)?));
+                }
+                ("MAND", [num_inputs, num_outputs, rest @ ..]) => {
+                    let m = parse_usize(num_outputs)?;
+                    if parse_usize(num_inputs)? != 2 * m || rest.len() != 3 * m {
+                        return Err(invalid(format!("malformed gate `{}`", line)));
+                    }
+                    for i in 0..m {
+                        gates.push(Gate::And(wire(rest[i])?, wire(rest[m + i])?, wire(rest[2 * m + i])?));
+                    }
+                }
+                _ => return Err(invalid(format!("unsupported gate `{}`", line))),
+            }
+        }
+
+        if num_gate_lines != num_gates {
+            return Err(invalid(format!("header announces {} gates, found {}", num_gates, num_gate_lines)));
+        }
+
+        let bristol = Bristol { num_wires, input_sizes, output_sizes, gates };
+        if bristol.num_inputs() + bristol.num_outputs() > num_wires {
+            return Err(invalid("more input and output wires than wires"));
+        }
+        Ok(bristol)
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.input_sizes.iter().sum()
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.output_sizes.iter().sum()
+    }
+
+    /// Value of every wire for the given input bits.
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        let mut values = vec![false; self.num_wires];
+        values[..inputs.len()].copy_from_slice(inputs);
+        for gate in &self.gates {
+            match *gate {
+                Gate::And(a, b, c) => values[c] = values[a] & values[b],
+                Gate::Xor(a, b, c) => values[c] = values[a] ^ values[b],
+                Gate::Inv(a, c) => values[c] = !values[a],
+                Gate::Eqw(a, c) => values[c] = values[a],
+                Gate::Eq(value, c) => values[c] = value,
+            }
+        }
+        values
+    }
+}
+
+/// R1CS version of a Bristol Fashion circuit. Input bits are boolean-constrained
+/// witnesses and output bits are public inputs. AND costs one multiplication,
+/// INV and EQW are linear combinations of their input, and XOR is the linear
+/// combination `a + b - 2ab`, which costs the single product `2a * b`.
+#[derive(Clone)]
+pub struct BristolCircuit<F: Field> {
+    bristol: Rc<Bristol>,
+    values: Option<Vec<bool>>,
+    print: bool,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> BristolCircuit<F> {
+    pub fn new(bristol: Rc<Bristol>, inputs: &[bool], printing: bool) -> io::Result<Self> {
+        expect_len("inputs", inputs, bristol.num_inputs())?;
+        let values = bristol.evaluate(inputs);
+        Ok(BristolCircuit { values: Some(values), ..Self::blank(bristol, printing) })
+    }
+
+    /// Circuit of the same shape as `new` with no input bits.
+    pub fn blank(bristol: Rc<Bristol>, printing: bool) -> Self {
+        BristolCircuit { bristol, values: None, print: printing, _field: std::marker::PhantomData }
+    }
+
+    fn load_params(params: &CircuitParams) -> io::Result<Bristol> {
+        let path = params.get("path").ok_or_else(|| invalid("use --circuit bristol:<file>"))?;
+        Bristol::load(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))
+    }
+
+    fn from_params<R: RngCore>(rng: &mut R, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let bristol = Self::load_params(params)?;
+        let inputs: Vec<bool> = match params.get("inputs") {
+            Some(bits) => bits
+                .chars()
+                .filter(|c| *c != '_')
+                .map(|c| match c {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(invalid(format!("input bits must be 0 or 1, found `{}`", c))),
+                })
+                .collect::<io::Result<_>>()?,
+            None => (0..bristol.num_inputs()).map(|_| rng.next_u32() & 1 == 1).collect(),
+        };
+        Self::new(Rc::new(bristol), &inputs, printing)
+    }
+
+    fn output_wires(&self) -> std::ops::Range<usize> {
+        (self.bristol.num_wires - self.bristol.num_outputs())..self.bristol.num_wires
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for BristolCircuit<F> {
+    fn name() -> &'static str {
+        "bristol"
+    }
+
+    fn description() -> &'static str {
+        "Boolean circuit loaded from a Bristol Fashion file"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "path", default: "", description: "Circuit file, given as --circuit bristol:<file>" },
+            ParamSpec { key: "inputs", default: "random", description: "Input bits, e.g. 0110_1001" },
+        ]
+    }
+
+    /// The circuit comes from `--circuit bristol:<file>`. Input bits are random
+    /// unless given as `--params inputs=0110...` (`_` may separate values).
+    fn new_random<R: RngCore>(rng: &mut R, _constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        or_exit(Self::from_params(rng, params, printing), "cannot build Bristol circuit")
+    }
+
+    /// Only reads the circuit file.
+    fn new_blank(_constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let bristol = or_exit(Self::load_params(params), "cannot build Bristol circuit");
+        Self::blank(Rc::new(bristol), printing)
+    }
+
+    /// The input bits replace `--params inputs`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let bristol = Self::load_params(params)?;
+        Self::new(Rc::new(bristol), &witness.bits("inputs")?, printing)
+    }
+
+    fn witness(&self) -> Witness {
+        let values = self.values.as_ref().expect("no value for `inputs` to export");
+        let mut witness = Witness::new(Self::name());
+        witness.set_bits("inputs", &values[..self.bristol.num_inputs()]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        BristolCircuit { values: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("gates", self.bristol.gates.len()), ("wires", self.bristol.num_wires)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for BristolCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let values = self.values.as_ref();
+        let value = |wire: usize| values.map(|v| bit(v[wire])).ok_or(SynthesisError::AssignmentMissing);
+        let two = ConstraintF::from(2u64);
+
+        let mut wires: Vec<LinearCombination<ConstraintF>> = vec![lc!(); self.bristol.num_wires];
+        for (wire, input_lc) in wires.iter_mut().enumerate().take(self.bristol.num_inputs()) {
+            let input = cs.new_witness_variable(|| value(wire))?;
+            cs.enforce_constraint(lc!() + input, lc!() + Variable::One - input, lc!())?;
+            *input_lc = lc!() + input;
+        }
+
+        for gate in &self.bristol.gates {
+            match *gate {
+                Gate::And(a, b, c) => {
+                    let out = cs.new_witness_variable(|| value(c))?;
+                    cs.enforce_constraint(wires[a].clone(), wires[b].clone(), lc!() + out)?;
+                    wires[c] = lc!() + out;
+                }
+                Gate::Xor(a, b, c) => {
+                    let out = cs.new_witness_variable(|| value(c))?;
+                    cs.enforce_constraint(&wires[a] * two, wires[b].clone(), &wires[a] + &wires[b] - out)?;
+                    wires[c] = lc!() + out;
+                }
+                Gate::Inv(a, c) => wires[c] = lc!() + Variable::One - &wires[a],
+                Gate::Eqw(a, c) => wires[c] = wires[a].clone(),
+                Gate::Eq(value, c) => wires[c] = if value { lc!() + Variable::One } else { lc!() },
+            }
+        }
+
+        for wire in self.output_wires() {
+            let output = cs.new_input_variable(|| value(wire))?;
+            cs.enforce_constraint(wires[wire].clone(), lc!() + Variable::One, lc!() + output)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+fn bit<F: Field>(value: bool) -> F {
+    if value { F::one() } else { F::zero() }
+}
+
+fn parse_usize(token: &str) -> io::Result<usize> {
+    token.parse().map_err(|_| invalid(format!("invalid number `{}`", token)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as BlsFr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Two 2-bit inputs `x` and `y`, and the outputs
+    /// `o0 = !(x0 ^ x1) & (y0 & y1)` and `o1 = (x0 ^ x1) & (y0 & y1)`.
+    const SMALL: &str = "4 9\n2 2 2\n2 1 1\n\n2 1 0 1 4 XOR\n2 1 2 3 5 AND\n1 1 4 6 INV\n4 2 6 4 5 5 7 8 MAND\n";
+
+    fn circuit(inputs: &[bool]) -> BristolCircuit<BlsFr> {
+        BristolCircuit::new(Rc::new(Bristol::parse(SMALL).unwrap()), inputs, false).unwrap()
+    }
+
+    #[test]
+    fn evaluates_and_satisfies() {
+        for (inputs, outputs) in [([true, false, true, true], [0u64, 1]), ([false, false, true, true], [1, 0])] {
+            let circuit = circuit(&inputs);
+            let expected: Vec<BlsFr> = outputs.iter().copied().map(BlsFr::from).collect();
+            assert_eq!(circuit.get_public_inputs(), expected);
+
+            let cs = ConstraintSystem::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            assert_eq!(cs.num_instance_variables(), 3);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_output() {
+        let mut circuit = circuit(&[true, false, true, true]);
+        if let Some(values) = circuit.values.as_mut() {
+            values[8] = false;
+        }
+        let cs = ConstraintSystem::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for text in [
+            "",
+            "4 9\n2 2 2\n",
+            "4 9\n3 2 2\n2 1 1\n2 1 0 1 4 XOR\n2 1 2 3 5 AND\n1 1 4 6 INV\n4 2 6 4 5 5 7 8 MAND\n",
+            "5 9\n2 2 2\n2 1 1\n2 1 0 1 4 XOR\n2 1 2 3 5 AND\n1 1 4 6 INV\n4 2 6 4 5 5 7 8 MAND\n",
+            "4 9\n2 2 2\n2 1 1\n2 1 0 1 9 XOR\n2 1 2 3 5 AND\n1 1 4 6 INV\n4 2 6 4 5 5 7 8 MAND\n",
+            "4 9\n2 2 2\n2 1 1\n2 1 0 1 4 OR\n2 1 2 3 5 AND\n1 1 4 6 INV\n4 2 6 4 5 5 7 8 MAND\n",
+            "4 9\n2 2 2\n2 1 1\n2 1 0 1 4 XOR\n2 1 2 3 5 AND\n1 1 4 6 INV\n4 2 6 4 5 5 7 MAND\n",
+            "4 9\n2 2 2\n2 1 1\n2 1 0 1 4 XOR\n2 1 2 3 5 AND\n1 1 4 6 INV\n3 2 6 4 5 5 7 8 MAND\n",
+            "1 2\n1 1\n1 1\n1 1 2 1 EQ\n",
+        ] {
+            assert!(Bristol::parse(text).is_err(), "accepted {:?}", text);
+        }
+        let bristol = Rc::new(Bristol::parse(SMALL).unwrap());
+        assert!(BristolCircuit::<BlsFr>::new(bristol, &[true, false, true], false).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use ark_ff::{FpParameters, PrimeField};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use num_bigint::BigUint;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{or_exit, print_stats, BenchCircuit, ParamSpec};
+use super::witness::{invalid, Witness};
+
+type Terms<F> = Vec<(usize, F)>;
+
+/// Constraint system read from a circom `.r1cs` file. Wire 0 is the constant
+/// one, followed by the public outputs and public inputs.
+pub struct CircomR1cs<F: PrimeField> {
+    pub num_wires: usize,
+    pub num_public: usize,
+    pub constraints: Vec<(Terms<F>, Terms<F>, Terms<F>)>,
+}
+
+#[derive(Clone)]
+pub struct CircomCircuit<F: PrimeField> {
+    r1cs: Rc<CircomR1cs<F>>,
+    witness: Option<Rc<Vec<F>>>,
+    print: bool,
+}
+
+impl<F: PrimeField> CircomCircuit<F> {
+    /// `witness` holds the value of every wire, starting with the constant one.
+    pub fn new(r1cs: Rc<CircomR1cs<F>>, witness: Vec<F>, printing: bool) -> io::Result<Self> {
+        if witness.len() != r1cs.num_wires {
+            return Err(invalid(format!(
+                "witness has {} values but the circuit has {} wires",
+                witness.len(),
+                r1cs.num_wires,
+            )));
+        }
+        if witness.first() != Some(&F::one()) {
+            return Err(invalid("wire 0 of the witness must be the constant one"));
+        }
+        Ok(CircomCircuit { witness: Some(Rc::new(witness)), ..Self::blank(r1cs, printing) })
+    }
+
+    /// Circuit of the same shape as `new` with no wire values.
+    pub fn blank(r1cs: Rc<CircomR1cs<F>>, printing: bool) -> Self {
+        CircomCircuit { r1cs, witness: None, print: printing }
+    }
+
+    /// Loads a circuit from its `.r1cs` file and the `.wtns` file produced by
+    /// the circom witness generator.
+    pub fn load<P: AsRef<Path>>(r1cs_path: P, wtns_path: P) -> io::Result<Self> {
+        let r1cs = read_r1cs(&fs::read(r1cs_path)?)?;
+        let witness = read_wtns(&fs::read(wtns_path)?)?;
+        Self::new(Rc::new(r1cs), witness, false)
+    }
+
+    fn r1cs_path(params: &CircuitParams) -> &str {
+        let path = params.get("path").ok_or_else(|| invalid("use --circuit circom:<file.r1cs>"));
+        or_exit(path, "missing .r1cs file")
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for CircomCircuit<F> {
+    fn name() -> &'static str {
+        "circom"
+    }
+
+    fn description() -> &'static str {
+        "Circuit loaded from circom .r1cs and .wtns files"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "path", default: "", description: ".r1cs file, given as --circuit circom:<file>" },
+            ParamSpec { key: "wtns", default: "<path>.wtns", description: "Witness file" },
+        ]
+    }
+
+    /// The circuit and its witness come from the files given with
+    /// `--circuit circom:<file.r1cs>` and, optionally, `--params wtns=<file.wtns>`.
+    fn new_random<R: RngCore>(_rng: &mut R, _constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let r1cs_path = Self::r1cs_path(params);
+        let wtns_path = match params.get("wtns") {
+            Some(path) => path.to_string(),
+            None => Path::new(r1cs_path).with_extension("wtns").to_string_lossy().into_owned(),
+        };
+        let mut circuit = or_exit(
+            Self::load(r1cs_path, wtns_path.as_str()),
+            &format!("cannot load circom circuit {}", r1cs_path),
+        );
+        circuit.print = printing;
+        circuit
+    }
+
+    /// Only reads the `.r1cs` file.
+    fn new_blank(_constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let r1cs_path = Self::r1cs_path(params);
+        let r1cs = or_exit(
+            fs::read(r1cs_path).and_then(|bytes| read_r1cs(&bytes)),
+            &format!("cannot load circom circuit {}", r1cs_path),
+        );
+        Self::blank(Rc::new(r1cs), printing)
+    }
+
+    /// The wire values, in the order of the `.wtns` file, replace the `wtns`
+    /// file.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let r1cs = read_r1cs(&fs::read(Self::r1cs_path(params))?)?;
+        Self::new(Rc::new(r1cs), witness.fields("wires")?, printing)
+    }
+
+    fn witness(&self) -> Witness {
+        let wires = self.witness.as_ref().expect("no value for `wires` to export");
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("wires", wires.iter().copied().map(Some));
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        CircomCircuit { witness: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("constraints", self.r1cs.constraints.len()),
+            ("wires", self.r1cs.num_wires),
+            ("public", self.r1cs.num_public),
+        ]
+    }
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for CircomCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let witness = self.witness.as_deref();
+        let value = |wire: usize| witness.map(|w| w[wire]).ok_or(SynthesisError::AssignmentMissing);
+
+        let mut variables = Vec::with_capacity(self.r1cs.num_wires);
+        variables.push(Variable::One);
+        for wire in 1..=self.r1cs.num_public {
+            variables.push(cs.new_input_variable(|| value(wire))?);
+        }
+        for wire in (self.r1cs.num_public + 1)..self.r1cs.num_wires {
+            variables.push(cs.new_witness_variable(|| value(wire))?);
+        }
+
+        let to_lc = |terms: &Terms<ConstraintF>| {
+            terms.iter().fold(lc!(), |lc: LinearCombination<ConstraintF>, (wire, coeff)| lc + (*coeff, variables[*wire]))
+        };
+        for (a, b, c) in &self.r1cs.constraints {
+            cs.enforce_constraint(to_lc(a), to_lc(b), to_lc(c))?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+/// Parses the binary `.r1cs` format (version 1) written by circom.
+pub fn read_r1cs<F: PrimeField>(bytes: &[u8]) -> io::Result<CircomR1cs<F>> {
+    let sections = read_sections(bytes, b"r1cs", &[1])?;
+
+    let mut header = Reader::new(section(&sections, 1)?);
+    let field_size = header.u32()? as usize;
+    check_prime::<F>(header.take(field_size)?)?;
+    let num_wires = header.u32()? as usize;
+    let num_public_outputs = header.u32()? as usize;
+    let num_public_inputs = header.u32()? as usize;
+    let _num_private_inputs = header.u32()?;
+    let _num_labels = header.u64()?;
+    let num_constraints = header.u32()? as usize;
+
+    let mut body = Reader::new(section(&sections, 2)?);
+    let mut read_terms = || -> io::Result<Terms<F>> {
+        let num_terms = body.u32()? as usize;
+        let mut terms = Vec::new();
+        for _ in 0..num_terms {
+            let wire = body.u32()? as usize;
+            if wire >= num_wires {
+                return Err(invalid(format!("wire {} out of range", wire)));
+            }
+            terms.push((wire, body.field(field_size)?));
+        }
+        Ok(terms)
+    };
+    let mut constraints = Vec::new();
+    for _ in 0..num_constraints {
+        constraints.push((read_terms()?, read_terms()?, read_terms()?));
+    }
+
+    Ok(CircomR1cs {
+        num_wires,
+        num_public: num_public_outputs + num_public_inputs,
+        constraints,
+    })
+}
+
+/// Parses the binary `.wtns` format written by the circom witness generator.
+pub fn read_wtns<F: PrimeField>(bytes: &[u8]) -> io::Result<Vec<F>> {
+    let sections = read_sections(bytes, b"wtns", &[1, 2])?;
+
+    let mut header = Reader::new(section(&sections, 1)?);
+    let field_size = header.u32()? as usize;
+    check_prime::<F>(header.take(field_size)?)?;
+    let num_values = header.u32()? as usize;
+
+    let body = section(&sections, 2)?;
+    if num_values.checked_mul(field_size) != Some(body.len()) {
+        return Err(invalid(format!("witness section does not hold {} values", num_values)));
+    }
+    let mut body = Reader::new(body);
+    (0..num_values).map(|_| body.field(field_size)).collect()
+}
+
+fn read_sections<'a>(bytes: &'a [u8], magic: &[u8], versions: &[u32]) -> io::Result<HashMap<u32, &'a [u8]>> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(4)? != magic {
+        return Err(invalid(format!("not a {} file", String::from_utf8_lossy(magic))));
+    }
+    let version = reader.u32()?;
+    if !versions.contains(&version) {
+        return Err(invalid(format!("unsupported {} version {}", String::from_utf8_lossy(magic), version)));
+    }
+    let num_sections = reader.u32()?;
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let section_type = reader.u32()?;
+        let size = reader.u64()? as usize;
+        sections.insert(section_type, reader.take(size)?);
+    }
+    Ok(sections)
+}
+
+fn section<'a>(sections: &HashMap<u32, &'a [u8]>, section_type: u32) -> io::Result<&'a [u8]> {
+    sections.get(&section_type).copied().ok_or_else(|| invalid(format!("missing section {}", section_type)))
+}
+
+fn check_prime<F: PrimeField>(prime: &[u8]) -> io::Result<()> {
+    let modulus: BigUint = F::Params::MODULUS.into();
+    let prime = BigUint::from_bytes_le(prime);
+    if prime == modulus {
+        Ok(())
+    } else {
+        Err(invalid(format!("circuit prime {} does not match the field modulus {}", prime, modulus)))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated circom file"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads a field element, rejecting values that are not reduced modulo
+    /// the field's prime instead of silently reducing them.
+    fn field<F: PrimeField>(&mut self, size: usize) -> io::Result<F> {
+        let value = BigUint::from_bytes_le(self.take(size)?);
+        let modulus: BigUint = F::Params::MODULUS.into();
+        if value >= modulus {
+            return Err(invalid(format!("field element {} is not below the modulus", value)));
+        }
+        Ok(F::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as BlsFr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const FIELD_SIZE: usize = 32;
+    const BN254_PRIME: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+    fn bls_prime() -> BigUint {
+        <BlsFr as PrimeField>::Params::MODULUS.into()
+    }
+
+    fn field_bytes(value: &BigUint) -> Vec<u8> {
+        let mut bytes = value.to_bytes_le();
+        bytes.resize(FIELD_SIZE, 0);
+        bytes
+    }
+
+    fn file(magic: &[u8], version: u32, sections: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend(version.to_le_bytes());
+        bytes.extend((sections.len() as u32).to_le_bytes());
+        for (section_type, body) in sections {
+            bytes.extend(section_type.to_le_bytes());
+            bytes.extend((body.len() as u64).to_le_bytes());
+            bytes.extend(body);
+        }
+        bytes
+    }
+
+    /// `out = x * y` with wires `[one, out, x, y]`, `out` public.
+    fn r1cs(prime: &BigUint) -> Vec<u8> {
+        let mut header = (FIELD_SIZE as u32).to_le_bytes().to_vec();
+        header.extend(field_bytes(prime));
+        for value in [4u32, 1, 0, 2] {
+            header.extend(value.to_le_bytes());
+        }
+        header.extend(4u64.to_le_bytes());
+        header.extend(1u32.to_le_bytes());
+
+        let mut body = Vec::new();
+        for wire in [2u32, 3, 1] {
+            body.extend(1u32.to_le_bytes());
+            body.extend(wire.to_le_bytes());
+            body.extend(field_bytes(&BigUint::from(1u32)));
+        }
+        file(b"r1cs", 1, &[(1, header), (2, body)])
+    }
+
+    fn wtns(prime: &BigUint, values: &[u64]) -> Vec<u8> {
+        let mut header = (FIELD_SIZE as u32).to_le_bytes().to_vec();
+        header.extend(field_bytes(prime));
+        header.extend((values.len() as u32).to_le_bytes());
+        let body = values.iter().flat_map(|value| field_bytes(&BigUint::from(*value))).collect();
+        file(b"wtns", 2, &[(1, header), (2, body)])
+    }
+
+    #[test]
+    fn reads_r1cs_and_wtns() {
+        let r1cs = read_r1cs::<BlsFr>(&r1cs(&bls_prime())).unwrap();
+        assert_eq!((r1cs.num_wires, r1cs.num_public, r1cs.constraints.len()), (4, 1, 1));
+        let witness = read_wtns::<BlsFr>(&wtns(&bls_prime(), &[1, 6, 2, 3])).unwrap();
+        assert_eq!(witness, [1u64, 6, 2, 3].map(BlsFr::from).to_vec());
+
+        let circuit = CircomCircuit::new(Rc::new(r1cs), witness, false).unwrap();
+        assert_eq!(circuit.get_public_inputs(), vec![BlsFr::from(6u64)]);
+        let cs = ConstraintSystem::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn rejects_wrong_magic_or_version() {
+        let mut bytes = r1cs(&bls_prime());
+        bytes[..4].copy_from_slice(b"wtns");
+        assert!(read_r1cs::<BlsFr>(&bytes).is_err());
+
+        let mut bytes = r1cs(&bls_prime());
+        bytes[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert!(read_r1cs::<BlsFr>(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_section() {
+        let bytes = r1cs(&bls_prime());
+        let error = read_r1cs::<BlsFr>(&bytes[..bytes.len() - 1]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let bytes = wtns(&bls_prime(), &[1, 6, 2, 3]);
+        assert!(read_wtns::<BlsFr>(&bytes[..bytes.len() - FIELD_SIZE]).is_err());
+    }
+
+    #[test]
+    fn rejects_other_prime() {
+        let bn254 = BigUint::parse_bytes(BN254_PRIME.as_bytes(), 10).unwrap();
+        assert!(check_prime::<BlsFr>(&field_bytes(&bn254)).is_err());
+        assert!(read_r1cs::<BlsFr>(&r1cs(&bn254)).is_err());
+        assert!(read_wtns::<BlsFr>(&wtns(&bn254, &[1, 6, 2, 3])).is_err());
+    }
+
+    #[test]
+    fn rejects_unreduced_values() {
+        let mut bytes = wtns(&bls_prime(), &[1, 6, 2, 3]);
+        let last = bytes.len() - FIELD_SIZE;
+        bytes[last..].copy_from_slice(&field_bytes(&bls_prime()));
+        let error = read_wtns::<BlsFr>(&bytes).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_witness_without_constant_one() {
+        let r1cs = Rc::new(read_r1cs::<BlsFr>(&r1cs(&bls_prime())).unwrap());
+        let witness = read_wtns::<BlsFr>(&wtns(&bls_prime(), &[2, 12, 2, 3])).unwrap();
+        assert!(CircomCircuit::new(r1cs, witness, false).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+use super::circuit_traits::or_exit;
+use super::witness::invalid;
+
+/// Circuit specific options given on the command line as `key=value,key=value`.
+/// The part after `:` in `--circuit name:path` is stored under `path`.
+#[derive(Clone, Debug, Default)]
+pub struct CircuitParams {
+    values: HashMap<String, String>,
+}
+
+impl CircuitParams {
+    pub fn parse(input: &str) -> io::Result<Self> {
+        let mut params = CircuitParams::default();
+        for entry in input.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            match entry.split_once('=') {
+                Some((key, value)) => params.insert(key.trim(), value.trim()),
+                None => return Err(invalid(format!("expected key=value, found `{}`", entry))),
+            }
+        }
+        Ok(params)
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Parsed value of `key`, if it was given.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> io::Result<Option<T>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid value `{}` for circuit parameter `{}`", value, key)))
+            })
+            .transpose()
+    }
+
+    /// Parsed value of `key`, or `default` when it was not given. Exits on a
+    /// value that does not parse.
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> T {
+        or_exit(self.get_parsed(key), "invalid --params").unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_malformed_params() {
+        assert!(CircuitParams::parse("bits=8,batch").is_err());
+        let params = CircuitParams::parse("bits=eight").unwrap();
+        assert!(params.get_parsed::<usize>("bits").is_err());
+        assert_eq!(params.get_parsed::<usize>("batch").unwrap(), None);
+    }
+}
@@ -1,7 +1,285 @@
-use ark_ff::Field;
+use ark_ff::{Field, PrimeField};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, LinearCombination, SynthesisError, SynthesisMode, Variable,
+};
 use ark_std::rand::RngCore;
+use colored::Colorize;
+use std::io;
+use std::time::Duration;
 
-pub trait BenchCircuit<F: Field> {
-    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, printing: bool) -> Self;
-    fn get_result(&self) -> F;
-}
\ No newline at end of file
+use super::circuit_params::CircuitParams;
+use super::witness::{invalid, Witness};
+
+/// A `--params` key understood by a circuit.
+pub struct ParamSpec {
+    pub key: &'static str,
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+/// A benchmark circuit. The harness proves the same instance several times and
+/// `prove` takes the circuit by value, so it clones the instance before each
+/// proof, outside the timed part. Circuits only need `Clone`, which lets them
+/// hold their witness in vectors.
+pub trait BenchCircuit<F: PrimeField>: ConstraintSynthesizer<F> + Clone {
+    /// Name used to select the circuit with `--circuit`.
+    fn name() -> &'static str;
+    fn description() -> &'static str;
+
+    /// Keys the circuit reads from `--params`.
+    fn params() -> &'static [ParamSpec] {
+        &[]
+    }
+
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self;
+
+    /// Circuit with the shape `new_random` builds for the same size and every
+    /// witness value `None`, for indexing and setup. It only computes the
+    /// shape: no witness is drawn and no witness file is read.
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self;
+
+    /// The same circuit with every witness value `None`, for when the shape
+    /// comes from an existing instance, such as one read with `--witness`.
+    fn into_blank(self) -> Self;
+
+    /// Builds the circuit from explicit values, as written by `witness`. The
+    /// values fix every size they determine and the rest comes from
+    /// `--constraints` and `--params` as in `new_random`.
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self>;
+
+    /// Values the circuit was built from, which `from_witness` turns back into
+    /// the same circuit.
+    fn witness(&self) -> Witness;
+
+    /// Size of the circuit actually built, after clamping `--constraints` and
+    /// `--params`, as named quantities.
+    fn effective_size(&self) -> Vec<(&'static str, usize)>;
+
+    /// Values of every instance variable, in allocation order, taken from
+    /// `public_inputs` so they always match the constraints. Panics on a blank
+    /// circuit.
+    fn get_public_inputs(&self) -> Vec<F> {
+        public_inputs(self.clone()).unwrap()
+    }
+}
+
+/// Synthesizes `circuit` in prove mode and returns its instance assignment
+/// without the leading one, so the public inputs always match the
+/// constraints instead of a separate formula.
+pub fn public_inputs<F: Field, C: ConstraintSynthesizer<F>>(circuit: C) -> Result<Vec<F>, SynthesisError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(SynthesisMode::Prove { construct_matrices: false });
+    circuit.generate_constraints(cs.clone())?;
+    let cs = cs.borrow().unwrap();
+    Ok(cs.instance_assignment[1..].to_vec())
+}
+
+/// Circuit to benchmark: built from the witness file given with `--witness`,
+/// stored under the `witness` key of `params`, or random otherwise.
+pub fn new_instance<F: PrimeField, C: BenchCircuit<F>, R: RngCore>(
+    rng: &mut R,
+    constraints: usize,
+    params: &CircuitParams,
+    printing: bool,
+) -> C {
+    let path = match params.get("witness") {
+        Some(path) => path,
+        None => return C::new_random(rng, constraints, params, printing),
+    };
+    let witness = or_exit(Witness::load(path), &format!("cannot read witness {}", path));
+    if witness.circuit != C::name() {
+        let message = format!("it is for the {} circuit, not {}", witness.circuit, C::name());
+        or_exit::<()>(Err(invalid(message)), &format!("cannot use witness {}", path));
+    }
+    or_exit(
+        C::from_witness(&witness, constraints, params, printing),
+        &format!("cannot build {} from witness {}", C::name(), path),
+    )
+}
+
+/// Unwraps the result of reading a file given on the command line. A bad file
+/// is a usage error rather than a bug, so it is reported and the process exits
+/// instead of panicking.
+pub fn or_exit<T>(result: io::Result<T>, context: &str) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{} {}: {}", Colorize::bold(Colorize::red("error:")), context, e);
+        std::process::exit(1)
+    })
+}
+
+/// Blank circuit to index or set up, with the shape of the circuits
+/// `new_instance` builds for the same arguments.
+pub fn new_blank_instance<F: PrimeField, C: BenchCircuit<F>>(constraints: usize, params: &CircuitParams, printing: bool) -> C {
+    match params.get("witness") {
+        Some(_) => new_instance::<F, C, _>(&mut ark_std::test_rng(), constraints, params, printing).into_blank(),
+        None => C::new_blank(constraints, params, printing),
+    }
+}
+
+/// Circuit and public inputs to prove in one benchmark iteration: a clone of
+/// `instance`, or with `fresh` a new random draw of the same shape, so the
+/// index built for `instance` still applies.
+pub fn iteration_instance<F, C, R>(
+    instance: &C,
+    public_inputs: &[F],
+    fresh: bool,
+    rng: &mut R,
+    constraints: usize,
+    params: &CircuitParams,
+) -> (C, Vec<F>)
+where
+    F: PrimeField,
+    C: BenchCircuit<F>,
+    R: RngCore,
+{
+    if fresh {
+        let circuit = C::new_random(rng, constraints, params, false);
+        let inputs = circuit.get_public_inputs();
+        (circuit, inputs)
+    } else {
+        (instance.clone(), public_inputs.to_vec())
+    }
+}
+
+/// Base `r` of the public digest `sum_i r^i * c_i` that circuits can publish
+/// instead of every element of their result.
+const DIGEST_BASE: u64 = 7;
+
+pub fn digest<F: Field>(values: &[Option<F>]) -> Option<F> {
+    let base = F::from(DIGEST_BASE);
+    values.iter().rev().try_fold(F::zero(), |acc, value| Some(acc * base + (*value)?))
+}
+
+pub fn digest_lc<F: Field>(variables: &[Variable]) -> LinearCombination<F> {
+    let base = F::from(DIGEST_BASE);
+    let mut power = F::one();
+    let mut lc = LinearCombination::zero();
+    for variable in variables {
+        lc += (power, *variable);
+        power *= base;
+    }
+    lc
+}
+
+/// Allocates `count` padding witnesses holding the value of the witness `copy`
+/// and returns one term per constraint for the circuit to add to the C side of
+/// that constraint, which must not contain `copy` already. Without
+/// `constrained` every term is empty, so the padding appears in no constraint.
+/// With it, padding variable `p_i` for `i < constraints` goes alone into term
+/// `i` as `p_i - copy`, which is zero for the honest witness. Since `copy`
+/// stays in the A or B side of the circuit's own rows, changing `p_i`, `copy`,
+/// or both by the same amount unbalances the row. Each remaining `p_i` gets
+/// its own constraint `(p_i - copy) * 1 = 0`.
+pub fn padding<F: Field>(
+    cs: &ConstraintSystemRef<F>,
+    count: usize,
+    copy: (Variable, Option<F>),
+    constraints: usize,
+    constrained: bool,
+) -> Result<Vec<LinearCombination<F>>, SynthesisError> {
+    let (copy, copy_val) = copy;
+    let mut terms = vec![LinearCombination::zero(); constraints];
+    for i in 0..count {
+        let p = cs.new_witness_variable(|| copy_val.ok_or(SynthesisError::AssignmentMissing))?;
+        if !constrained {
+            continue;
+        }
+        let term = LinearCombination::zero() + p - copy;
+        match terms.get_mut(i) {
+            Some(slot) => *slot = term,
+            None => cs.enforce_constraint(term, LinearCombination::zero() + Variable::One, LinearCombination::zero())?,
+        }
+    }
+    Ok(terms)
+}
+
+/// Constraints that `padding` adds on top of the circuit's own.
+pub fn surplus_padding(count: usize, constraints: usize, constrained: bool) -> usize {
+    if constrained { count.saturating_sub(constraints) } else { 0 }
+}
+
+/// Synthesizes `circuit` in prove mode, applies `tamper` to the assignment and
+/// reports whether every constraint still holds, so tests can check both that
+/// the honest witness is accepted and that a wrong value is caught.
+#[cfg(test)]
+pub fn is_satisfied_after<F, C, T>(circuit: C, tamper: T) -> bool
+where
+    F: Field,
+    C: ConstraintSynthesizer<F>,
+    T: FnOnce(&mut ConstraintSystem<F>),
+{
+    let cs = ConstraintSystem::<F>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    tamper(&mut cs.borrow_mut().unwrap());
+    cs.is_satisfied().unwrap()
+}
+
+#[cfg(test)]
+pub fn is_satisfied<F: Field, C: ConstraintSynthesizer<F>>(circuit: C) -> bool {
+    is_satisfied_after(circuit, |_| {})
+}
+
+/// Name, description and `--params` keys of a circuit, for `--circuit list`.
+pub fn print_description<F: PrimeField, C: BenchCircuit<F>>() {
+    println!("{} {}", Colorize::bold(Colorize::green(C::name())), C::description());
+    for param in C::params() {
+        let default = if param.default.is_empty() { String::new() } else { format!(" (default: {})", param.default) };
+        println!("    {}: {}{}", param.key, param.description, default);
+    }
+}
+
+pub fn print_effective_size<F: PrimeField, C: BenchCircuit<F>>(circuit: &C) {
+    let size: Vec<String> = circuit.effective_size().iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+    println!("{} {} ({})", Colorize::green("Circuit:"), C::name(), size.join(", "));
+}
+
+/// Mean and standard deviation of a timed step over the iterations, such as
+/// "Prover time in Marlin", to compare runs with a fixed witness against
+/// `--fresh-witness` ones.
+pub fn print_spread(step: &str, fresh: bool, durations: &[Duration]) {
+    let n = durations.len() as f64;
+    let secs: Vec<f64> = durations.iter().map(Duration::as_secs_f64).collect();
+    let mean = secs.iter().sum::<f64>() / n;
+    let std_dev = (secs.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / n).sqrt();
+    let witness = if fresh { "fresh witness" } else { "fixed witness" };
+    println!(
+        "{} {:?} ± {:?} ({:.1}%, min {:?}, max {:?})",
+        Colorize::bold(Colorize::cyan(format!("{} ({}):", step, witness).as_str())),
+        Duration::from_secs_f64(mean),
+        Duration::from_secs_f64(std_dev),
+        100.0 * std_dev / mean,
+        durations.iter().min().unwrap(),
+        durations.iter().max().unwrap(),
+    );
+}
+
+pub fn print_stats<F: Field>(cs: &ConstraintSystemRef<F>) {
+    let num_variables = cs.num_instance_variables() + cs.num_witness_variables();
+    println!("{} {}", Colorize::green("Constraints:"), cs.num_constraints());
+    println!("{} {}", Colorize::green("Variables:"), num_variables);
+    let matrices = cs.to_matrices().unwrap();
+    println!("{} {}", Colorize::green("Num witness variables:"), cs.num_witness_variables());
+    println!("{} {}", Colorize::green("Num public inputs:"), cs.num_instance_variables() - 1);
+    println!(
+        "{} A: {}, B: {}, C: {}",
+        Colorize::blue("R1CS non-zeros -"),
+        matrices.a_num_non_zero,
+        matrices.b_num_non_zero,
+        matrices.c_num_non_zero,
+    );
+    let matrix_num_values = cs.num_constraints() * num_variables;
+    println!(
+        "{} A: {}, B: {}, C: {}",
+        Colorize::blue("R1CS zeros -"),
+        matrix_num_values - matrices.a_num_non_zero,
+        matrix_num_values - matrices.b_num_non_zero,
+        matrix_num_values - matrices.c_num_non_zero,
+    );
+    println!(
+        "{} A: {}%, B: {}%, C: {}%",
+        Colorize::blue("R1CS sparsity -"),
+        ((matrix_num_values - matrices.a_num_non_zero) * 100) / matrix_num_values,
+        (matrix_num_values - matrices.b_num_non_zero) * 100 / matrix_num_values,
+        (matrix_num_values - matrices.c_num_non_zero) * 100 / matrix_num_values,
+    );
+}
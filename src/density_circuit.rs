@@ -0,0 +1,318 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use std::io;
+use std::ops::RangeInclusive;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::{expect_len, Witness};
+use ark_ff::{Field, PrimeField};
+
+const DEFAULT_NONZEROS: usize = 2;
+const SHAPE_SEED: u64 = 0x44656e73;
+
+/// Entries `(variable, coefficient)` of one row in A, B and C. Variable 0 is
+/// the constant one, then come the public inputs, the free witnesses and the
+/// dependent witnesses, in allocation order.
+type Row<F> = [Vec<(usize, F)>; 3];
+
+/// Synthetic circuit where the number of constraints, variables, public inputs
+/// and non-zeros per row in A, B and C are independent. The public inputs and
+/// free witnesses are random. Each of the first constraints defines a new
+/// dependent witness, which appears in C with coefficient 1 and takes whatever
+/// value satisfies the row, and its A, B and the rest of C pick random
+/// variables defined before it. If there are more constraints than dependent
+/// witnesses, the remaining rows are copies of the first ones with A scaled by
+/// λ, B by μ and C by λμ, which keeps the density and the witness valid. The
+/// rows only depend on the parameters and the shape seed, so every instance
+/// shares the index.
+#[derive(Clone)]
+pub struct DensityCircuit<F: Field> {
+    /// Public inputs followed by the free witnesses.
+    free: Vec<Option<F>>,
+    public: usize,
+    constraints: usize,
+    dependents: usize,
+    /// Non-zeros per row in A, B and C, drawn uniformly from each range.
+    nonzeros: [RangeInclusive<usize>; 3],
+    shape_seed: u64,
+    print: bool,
+}
+
+impl<F: Field> DensityCircuit<F> {
+    /// Random public inputs and free witnesses.
+    pub fn new<R: RngCore>(
+        rng: &mut R,
+        constraints: usize,
+        variables: usize,
+        public: usize,
+        nonzeros: [RangeInclusive<usize>; 3],
+        shape_seed: u64,
+        printing: bool,
+    ) -> Self {
+        let free = (0..Self::num_free(constraints, variables, public)).map(|_| <F>::rand(rng)).collect();
+        Self::from_values(free, constraints, variables, public, nonzeros, shape_seed, printing)
+    }
+
+    /// `free` holds the public inputs followed by the free witnesses, as many
+    /// as `num_free` gives for the same sizes. The dependent witnesses are
+    /// solved from them.
+    pub fn from_values(
+        free: Vec<F>,
+        constraints: usize,
+        variables: usize,
+        public: usize,
+        nonzeros: [RangeInclusive<usize>; 3],
+        shape_seed: u64,
+        printing: bool,
+    ) -> Self {
+        assert_eq!(free.len(), Self::num_free(constraints, variables, public), "wrong number of free values");
+        DensityCircuit {
+            free: free.into_iter().map(Some).collect(),
+            ..Self::blank(constraints, variables, public, nonzeros, shape_seed, printing)
+        }
+    }
+
+    /// Circuit with the rows of `new` for the same sizes and no values.
+    pub fn blank(
+        constraints: usize,
+        variables: usize,
+        public: usize,
+        nonzeros: [RangeInclusive<usize>; 3],
+        shape_seed: u64,
+        printing: bool,
+    ) -> Self {
+        let (constraints, num_free, dependents) = Self::layout(constraints, variables, public);
+        DensityCircuit {
+            free: vec![None; num_free],
+            public,
+            constraints,
+            dependents,
+            nonzeros: nonzeros.map(|range| {
+                let start = (*range.start()).max(1);
+                start..=(*range.end()).max(start)
+            }),
+            shape_seed,
+            print: printing,
+        }
+    }
+
+    /// Number of public inputs and free witnesses.
+    pub fn num_free(constraints: usize, variables: usize, public: usize) -> usize {
+        Self::layout(constraints, variables, public).1
+    }
+
+    /// Clamped number of constraints, then the number of free values and of
+    /// dependent witnesses.
+    fn layout(constraints: usize, variables: usize, public: usize) -> (usize, usize, usize) {
+        let constraints = constraints.max(1);
+        let variables = variables.max(public + 1);
+        let dependents = constraints.min(variables - public);
+        (constraints, variables - dependents, dependents)
+    }
+
+    /// Sizes from `--params`, as described in `new_random`.
+    fn params_shape(constraints: usize, params: &CircuitParams) -> (usize, usize, [RangeInclusive<usize>; 3]) {
+        let public = params.get_or("public", 1);
+        let nonzeros = ["nonzeros_a", "nonzeros_b", "nonzeros_c"].map(|key| {
+            let count = params.get_or(key, DEFAULT_NONZEROS);
+            count..=count
+        });
+        let variables = params.get_or("variables", constraints.max(1) + public);
+        (variables, public, nonzeros)
+    }
+
+    /// Rows never repeat a variable, so the first ones may have fewer
+    /// non-zeros than requested while few variables are defined.
+    fn rows(&self) -> Vec<Row<F>> {
+        let mut rng = StdRng::seed_from_u64(self.shape_seed);
+        let mut defined: Vec<usize> = (0..=self.free.len()).collect();
+        let mut rows: Vec<Row<F>> = Vec::with_capacity(self.constraints);
+        for i in 0..self.constraints {
+            if i < self.dependents {
+                let [a_count, b_count, c_count] = self.nonzeros.clone().map(|range| rng.gen_range(range));
+                let mut pick = |defined: &mut Vec<usize>, count: usize| -> Vec<(usize, F)> {
+                    let count = count.min(defined.len());
+                    let (chosen, _) = defined.partial_shuffle(&mut rng, count);
+                    let chosen = chosen.to_vec();
+                    chosen.into_iter().map(|variable| (variable, F::rand(&mut rng))).collect()
+                };
+                let a = pick(&mut defined, a_count);
+                let b = pick(&mut defined, b_count);
+                let mut c = pick(&mut defined, c_count - 1);
+                let output = self.free.len() + 1 + i;
+                c.push((output, F::one()));
+                defined.push(output);
+                rows.push([a, b, c]);
+            } else {
+                let lambda = F::rand(&mut rng);
+                let mu = F::rand(&mut rng);
+                let scale = |entries: &[(usize, F)], factor: F| -> Vec<(usize, F)> {
+                    entries.iter().map(|(variable, coefficient)| (*variable, *coefficient * factor)).collect()
+                };
+                let [a, b, c] = &rows[i % self.dependents];
+                let row = [scale(a, lambda), scale(b, mu), scale(c, lambda * mu)];
+                rows.push(row);
+            }
+        }
+        rows
+    }
+
+    /// Value of every variable, solving each dependent witness from its row.
+    fn values(&self, rows: &[Row<F>]) -> Option<Vec<F>> {
+        let mut values: Vec<F> = std::iter::once(Some(F::one())).chain(self.free.iter().copied()).collect::<Option<_>>()?;
+        for [a, b, c] in rows.iter().take(self.dependents) {
+            let evaluate = |entries: &[(usize, F)]| -> F {
+                entries.iter().map(|(variable, coefficient)| *coefficient * values[*variable]).sum()
+            };
+            let (_, rest) = c.split_last().unwrap();
+            let output = evaluate(a) * evaluate(b) - evaluate(rest);
+            values.push(output);
+        }
+        Some(values)
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for DensityCircuit<F> {
+    fn name() -> &'static str {
+        "density"
+    }
+
+    fn description() -> &'static str {
+        "Synthetic R1CS with independently configurable density"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "variables", default: "constraints + public", description: "Number of variables" },
+            ParamSpec { key: "public", default: "1", description: "Number of public inputs" },
+            ParamSpec { key: "nonzeros_a", default: "2", description: "Non-zeros per row in A" },
+            ParamSpec { key: "nonzeros_b", default: "2", description: "Non-zeros per row in B" },
+            ParamSpec { key: "nonzeros_c", default: "2", description: "Non-zeros per row in C" },
+        ]
+    }
+
+    /// `--params variables=n,public=p,nonzeros_a=x,nonzeros_b=y,nonzeros_c=z`,
+    /// with `--constraints` constraints. By default one public input, one
+    /// dependent witness per constraint and 2 non-zeros per row in each matrix.
+    /// Variables do not count the constant one.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (variables, public, nonzeros) = Self::params_shape(constraints, params);
+        Self::new(rng, constraints, variables, public, nonzeros, SHAPE_SEED, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (variables, public, nonzeros) = Self::params_shape(constraints, params);
+        Self::blank(constraints, variables, public, nonzeros, SHAPE_SEED, printing)
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let (variables, public, nonzeros) = Self::params_shape(constraints, params);
+        let free = witness.fields("free")?;
+        expect_len("free", &free, Self::num_free(constraints, variables, public))?;
+        Ok(Self::from_values(free, constraints, variables, public, nonzeros, SHAPE_SEED, printing))
+    }
+
+    /// Only the public inputs and free witnesses, which fix the rest.
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("free", self.free.iter().copied());
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        DensityCircuit { free: vec![None; self.free.len()], ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("constraints", self.constraints),
+            ("variables", self.free.len() + self.dependents),
+            ("public", self.public),
+        ]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for DensityCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let rows = self.rows();
+        let values = self.values(&rows);
+        let value = |variable: usize| values.as_ref().map(|v| v[variable]).ok_or(SynthesisError::AssignmentMissing);
+
+        let mut variables = vec![Variable::One];
+        for variable in 1..=(self.free.len() + self.dependents) {
+            variables.push(if variable <= self.public {
+                cs.new_input_variable(|| value(variable))?
+            } else {
+                cs.new_witness_variable(|| value(variable))?
+            });
+        }
+
+        let to_lc = |entries: &[(usize, ConstraintF)]| -> LinearCombination<ConstraintF> {
+            entries.iter().fold(lc!(), |acc, (variable, coefficient)| acc + (*coefficient, variables[*variable]))
+        };
+        for [a, b, c] in &rows {
+            cs.enforce_constraint(to_lc(a), to_lc(b), to_lc(c))?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after};
+    use crate::r1cs_export::constraint_matrices;
+    use ark_bls12_381::Fr as BlsFr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn circuit(constraints: usize, variables: usize, public: usize) -> DensityCircuit<BlsFr> {
+        let rng = &mut ark_std::test_rng();
+        DensityCircuit::new(rng, constraints, variables, public, [3..=3, 2..=2, 4..=4], SHAPE_SEED, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        for (constraints, variables, public) in [(1, 1, 0), (16, 20, 3), (30, 20, 3), (10, 40, 5)] {
+            let circuit = circuit(constraints, variables, public);
+            let cs = ConstraintSystem::new_ref();
+            circuit.clone().generate_constraints(cs.clone()).unwrap();
+            assert_eq!(cs.num_constraints(), constraints);
+            assert_eq!(cs.num_instance_variables() - 1, public);
+            assert_eq!(cs.num_instance_variables() - 1 + cs.num_witness_variables(), variables.max(public + 1));
+            assert!(is_satisfied(circuit));
+        }
+    }
+
+    #[test]
+    fn repeated_rows_keep_density() {
+        let matrices = constraint_matrices(circuit(30, 20, 3)).unwrap();
+        let last = matrices.num_constraints - 1;
+        assert_eq!((matrices.a[last].len(), matrices.b[last].len(), matrices.c[last].len()), (3, 2, 4));
+    }
+
+    #[test]
+    fn rejects_wrong_dependent() {
+        assert!(!is_satisfied_after(circuit(16, 20, 3), |cs| *cs.witness_assignment.last_mut().unwrap() += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_any_wrong_dependent() {
+        // Each dependent witness appears in C of the row that defines it and in
+        // neither A nor B of that row, so changing it alone breaks the row.
+        let circuit = circuit(30, 20, 3);
+        let free_witnesses = circuit.free.len() - circuit.public;
+        for index in free_witnesses..(free_witnesses + circuit.dependents) {
+            assert!(!is_satisfied_after(circuit.clone(), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
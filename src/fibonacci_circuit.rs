@@ -0,0 +1,139 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::Witness;
+use ark_ff::{Field, PrimeField};
+
+/// Proves knowledge of the first two terms of a Fibonacci-like sequence whose
+/// term `steps + 1` is the public input. Each constraint `(f_{i-1} + f_i) * 1
+/// = f_{i+1}` consumes the outputs of the two previous ones.
+#[derive(Copy, Clone)]
+pub struct FibonacciCircuit<F: Field> {
+    first: Option<F>,
+    second: Option<F>,
+    steps: usize,
+    print: bool,
+}
+
+impl<F: Field> FibonacciCircuit<F> {
+    pub fn new(first: F, second: F, steps: usize, printing: bool) -> Self {
+        FibonacciCircuit { first: Some(first), second: Some(second), ..Self::blank(steps, printing) }
+    }
+
+    /// Circuit of the same shape as `new` with no initial terms.
+    pub fn blank(steps: usize, printing: bool) -> Self {
+        FibonacciCircuit { first: None, second: None, steps: steps.max(1), print: printing }
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for FibonacciCircuit<F> {
+    fn name() -> &'static str {
+        "fibonacci"
+    }
+
+    fn description() -> &'static str {
+        "Fibonacci-like sequence, one step per constraint"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "classic", default: "false", description: "Start from 0 and 1 instead of random terms" },
+        ]
+    }
+
+    /// One step per constraint. The first two terms are random, or 0 and 1 with
+    /// `--params classic=true`, in which case the result is the Fibonacci
+    /// number of index `--constraints + 1`.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (first, second) = if params.get_or("classic", false) {
+            (F::zero(), F::one())
+        } else {
+            (<F>::rand(rng), <F>::rand(rng))
+        };
+        Self::new(first, second, constraints, printing)
+    }
+
+    fn new_blank(constraints: usize, _params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(constraints, printing)
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, _params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        Ok(Self::new(witness.field("first")?, witness.field("second")?, constraints, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("first", [self.first]);
+        witness.set_fields("second", [self.second]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        FibonacciCircuit { first: None, second: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("steps", self.steps)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for FibonacciCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let mut a = cs.new_witness_variable(|| self.first.ok_or(SynthesisError::AssignmentMissing))?;
+        let mut b = cs.new_witness_variable(|| self.second.ok_or(SynthesisError::AssignmentMissing))?;
+        let mut a_val = self.first;
+        let mut b_val = self.second;
+
+        for step in 0..self.steps {
+            let next_val = a_val.zip(b_val).map(|(a, b)| a + b);
+            let next = if step + 1 == self.steps {
+                cs.new_input_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.new_witness_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce_constraint(lc!() + a + b, lc!() + Variable::One, lc!() + next)?;
+            a = b;
+            b = next;
+            a_val = b_val;
+            b_val = next_val;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit() -> FibonacciCircuit<BlsFr> {
+        FibonacciCircuit::new(BlsFr::from(0u64), BlsFr::from(1u64), 10, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(public_inputs(circuit()).unwrap(), vec![BlsFr::from(89u64)]);
+        assert!(is_satisfied(circuit()));
+    }
+
+    #[test]
+    fn rejects_broken_chain() {
+        // Witnesses are the two starting values, then the 9 intermediate terms.
+        for index in 0..11 {
+            assert!(!is_satisfied_after(circuit(), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
@@ -2,11 +2,13 @@ use ark_relations::{
     lc,
     r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
 };
-use colored::Colorize;
 use ark_std::rand::RngCore;
+use std::io;
 
-use super::circuit_traits::BenchCircuit;
-use ark_ff::Field;
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{padding, print_stats, surplus_padding, BenchCircuit, ParamSpec};
+use super::witness::Witness;
+use ark_ff::{Field, PrimeField};
 
 #[derive(Copy, Clone)]
 pub struct HadamardCircuit<F: Field> {
@@ -14,26 +16,89 @@ pub struct HadamardCircuit<F: Field> {
     b: Option<F>,
     num_constraints: usize,
     num_variables: usize,
+    constrain_padding: bool,
     print: bool,
 }
 
-impl<F: Field> BenchCircuit<F> for HadamardCircuit<F> {
-    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, printing: bool) -> Self {
-        HadamardCircuit { 
-            a: Some(<F>::rand(rng)), 
-            b: Some(<F>::rand(rng)), 
-            num_constraints: if constraints < 3 { 3 } else { constraints },
-            num_variables: if constraints < 3 { 3 } else { constraints },
+impl<F: Field> HadamardCircuit<F> {
+    pub fn new(a: F, b: F, constraints: usize, variables: usize, constrain_padding: bool, printing: bool) -> Self {
+        HadamardCircuit { a: Some(a), b: Some(b), ..Self::blank(constraints, variables, constrain_padding, printing) }
+    }
+
+    /// Circuit of the same shape as `new` with no witness.
+    pub fn blank(constraints: usize, variables: usize, constrain_padding: bool, printing: bool) -> Self {
+        HadamardCircuit {
+            a: None,
+            b: None,
+            num_constraints: constraints.max(3),
+            num_variables: variables.max(3),
+            constrain_padding,
             print: printing,
         }
     }
+}
 
-    fn get_result(&self) -> F {
-        if let (Some(a_val), Some(b_val)) = (self.a, self.b) {
-            return a_val * b_val;
-        } else {
-            return <F>::zero();
-        }
+impl<F: PrimeField> BenchCircuit<F> for HadamardCircuit<F> {
+    fn name() -> &'static str {
+        "hadamard"
+    }
+
+    fn description() -> &'static str {
+        "Repeats the constraint a * b = c"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "variables", default: "constraints", description: "Number of variables, independent of --constraints" },
+            ParamSpec { key: "constrain_padding", default: "false", description: "Tie the padding variables to the existing constraints" },
+        ]
+    }
+
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::new(
+            <F>::rand(rng),
+            <F>::rand(rng),
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        )
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        )
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        Ok(Self::new(
+            witness.field("a")?,
+            witness.field("b")?,
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        ))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("a", [self.a]);
+        witness.set_fields("b", [self.b]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        HadamardCircuit { a: None, b: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        let surplus = surplus_padding(self.num_variables - 3, self.num_constraints, self.constrain_padding);
+        vec![("constraints", self.num_constraints + surplus), ("variables", self.num_variables)]
     }
 } 
 
@@ -52,41 +117,67 @@ impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for HadamardCircuit<
             Ok(a)
         })?;
 
-        for _ in 0..(self.num_variables - 3) { 
-            let _ = cs.new_witness_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
-        }
+        let padding = padding(&cs, self.num_variables - 3, (a, self.a), self.num_constraints, self.constrain_padding)?;
 
-        for _ in 0..(self.num_constraints) {
-            cs.enforce_constraint(lc!() + a, lc!() + b, lc!() + c)?;
+        for term in &padding {
+            cs.enforce_constraint(lc!() + a, lc!() + b, lc!() + c + term)?;
         }
         if self.print == true{
-            println!("{} {}", Colorize::green("Constraints:"), cs.num_constraints());
-            println!("{} {}", Colorize::green("Variables:"), cs.num_constraints());
-            let matrices = cs.to_matrices().unwrap();
-            println!("{} {}", Colorize::green("Num witness variables:"), cs.num_witness_variables() + 1);
-            println!(
-                "{} A: {}, B: {}, C: {}", 
-                Colorize::blue("R1CS non-zeros -"),
-                matrices.a_num_non_zero,
-                matrices.b_num_non_zero,
-                matrices.c_num_non_zero,
-            );
-            let matrix_num_values = cs.num_constraints() * (cs.num_witness_variables() + 1);
-            println!(
-                "{} A: {}, B: {}, C: {}", 
-                Colorize::blue("R1CS zeros -"),
-                matrix_num_values - matrices.a_num_non_zero,
-                matrix_num_values - matrices.b_num_non_zero,
-                matrix_num_values - matrices.c_num_non_zero,
-            );
-            println!(
-                "{} A: {}%, B: {}%, C: {}%", 
-                Colorize::blue("R1CS sparsity -"),
-                ((matrix_num_values - matrices.a_num_non_zero) * 100) / matrix_num_values,
-                (matrix_num_values - matrices.b_num_non_zero) * 100 / matrix_num_values,
-                (matrix_num_values - matrices.c_num_non_zero) * 100 / matrix_num_values,
-            );
+            print_stats(&cs);
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after};
+    use ark_bls12_381::Fr as BlsFr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn circuit(constrain_padding: bool) -> HadamardCircuit<BlsFr> {
+        HadamardCircuit::new(BlsFr::from(3u64), BlsFr::from(5u64), 4, 20, constrain_padding, false)
+    }
+
+    fn shift(cs: &mut ConstraintSystem<BlsFr>, variables: std::ops::Range<usize>) {
+        for i in variables {
+            cs.witness_assignment[i] += BlsFr::from(1u64);
+        }
+    }
+
+    #[test]
+    fn every_padding_variable_is_constrained() {
+        assert!(is_satisfied(circuit(true)));
+        assert_eq!(circuit(true).effective_size(), vec![("constraints", 4 + 13), ("variables", 20)]);
+        // Witnesses are a, b and then the 17 padding variables.
+        for i in 2..19 {
+            assert!(!is_satisfied_after(circuit(true), |cs| shift(cs, i..i + 1)), "padding variable {} is free", i);
+        }
+        assert!(!is_satisfied_after(circuit(true), |cs| shift(cs, 0..1)), "a is free");
+        assert!(!is_satisfied_after(circuit(true), |cs| shift(cs, 2..19)), "the padding moves freely");
+        assert!(!is_satisfied_after(circuit(true), |cs| {
+            shift(cs, 0..1);
+            shift(cs, 2..19);
+        }), "a moves freely with the padding");
+    }
+
+    #[test]
+    fn fewer_padding_variables_than_constraints_are_constrained() {
+        let circuit = || HadamardCircuit::new(BlsFr::from(3u64), BlsFr::from(5u64), 4, 5, true, false);
+        assert!(is_satisfied(circuit()));
+        assert_eq!(circuit().effective_size(), vec![("constraints", 4), ("variables", 5)]);
+        assert!(!is_satisfied_after(circuit(), |cs| shift(cs, 2..3)));
+        assert!(!is_satisfied_after(circuit(), |cs| shift(cs, 0..1)));
+        assert!(!is_satisfied_after(circuit(), |cs| {
+            shift(cs, 0..1);
+            shift(cs, 2..4);
+        }));
+    }
+
+    #[test]
+    fn unconstrained_padding_is_free() {
+        assert_eq!(circuit(false).effective_size(), vec![("constraints", 4), ("variables", 20)]);
+        assert!(is_satisfied_after(circuit(false), |cs| *cs.witness_assignment.last_mut().unwrap() += BlsFr::from(1u64)));
+    }
+}
@@ -0,0 +1,185 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::{invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+const COEFFICIENTS_SEED: u64 = 0x486f726e6572;
+
+/// Evaluates a polynomial of degree d at a witness point with Horner's rule,
+/// one constraint `acc_{i+1} * x = acc_i - c_i` per coefficient below the
+/// leading one. The value is public. With `--params public_coefficients=true`
+/// the coefficients are public inputs too, otherwise they are constants in the
+/// matrices, taken from a fixed seed so every instance shares the same index.
+#[derive(Clone)]
+pub struct HornerCircuit<F: Field> {
+    /// `coefficients[i]` multiplies `x^i`.
+    coefficients: Vec<F>,
+    x: Option<F>,
+    public_coefficients: bool,
+    print: bool,
+}
+
+impl<F: Field> HornerCircuit<F> {
+    /// `coefficients[i]` multiplies `x^i`, and there must be at least two.
+    pub fn new(coefficients: Vec<F>, x: F, public_coefficients: bool, printing: bool) -> Self {
+        assert!(coefficients.len() >= 2, "degree must be at least 1");
+        HornerCircuit {
+            coefficients,
+            x: Some(x),
+            public_coefficients,
+            print: printing,
+        }
+    }
+
+    /// The constant coefficients every instance shares.
+    fn constant_coefficients(degree: usize) -> Vec<F> {
+        let mut seeded = StdRng::seed_from_u64(COEFFICIENTS_SEED);
+        (0..=degree).map(|_| <F>::rand(&mut seeded)).collect()
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for HornerCircuit<F> {
+    fn name() -> &'static str {
+        "horner"
+    }
+
+    fn description() -> &'static str {
+        "Polynomial evaluation at a witness point with Horner's rule"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "degree", default: "constraints", description: "Polynomial degree" },
+            ParamSpec { key: "public_coefficients", default: "false", description: "Coefficients as public inputs instead of constants" },
+        ]
+    }
+
+    /// The degree is `--params degree=d`, by default `--constraints`.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let degree = params.get_or("degree", constraints).max(1);
+        let public_coefficients = params.get_or("public_coefficients", false);
+        let coefficients = if public_coefficients {
+            (0..=degree).map(|_| <F>::rand(rng)).collect()
+        } else {
+            Self::constant_coefficients(degree)
+        };
+        Self::new(coefficients, <F>::rand(rng), public_coefficients, printing)
+    }
+
+    /// Public coefficients are only values of the instance, so they are left
+    /// at zero.
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let degree = params.get_or("degree", constraints).max(1);
+        let public_coefficients = params.get_or("public_coefficients", false);
+        let coefficients = if public_coefficients { vec![F::zero(); degree + 1] } else { Self::constant_coefficients(degree) };
+        HornerCircuit { coefficients, x: None, public_coefficients, print: printing }
+    }
+
+    /// The coefficients come from the witness even when they are constants,
+    /// and their number replaces `degree`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let coefficients = witness.fields("coefficients")?;
+        if coefficients.len() < 2 {
+            return Err(invalid("`coefficients` needs at least two values"));
+        }
+        let public_coefficients = params.get_or("public_coefficients", false);
+        Ok(Self::new(coefficients, witness.field("x")?, public_coefficients, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("coefficients", self.coefficients.iter().copied().map(Some));
+        witness.set_fields("x", [self.x]);
+        witness
+    }
+
+    /// The coefficients stay, since they are constants or public inputs.
+    fn into_blank(self) -> Self {
+        HornerCircuit { x: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("degree", self.coefficients.len() - 1)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for HornerCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let coefficients: Vec<LinearCombination<ConstraintF>> = if self.public_coefficients {
+            self.coefficients
+                .iter()
+                .map(|c| Ok(lc!() + cs.new_input_variable(|| Ok(*c))?))
+                .collect::<Result<_, SynthesisError>>()?
+        } else {
+            self.coefficients.iter().map(|c| lc!() + (*c, Variable::One)).collect()
+        };
+        let x = cs.new_witness_variable(|| self.x.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let degree = self.coefficients.len() - 1;
+        let mut acc = coefficients[degree].clone();
+        let mut acc_val = self.x.map(|_| self.coefficients[degree]);
+        for i in (0..degree).rev() {
+            let next_val = acc_val.zip(self.x).map(|(acc, x)| acc * x + self.coefficients[i]);
+            let next = if i == 0 {
+                cs.new_input_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.new_witness_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce_constraint(acc, lc!() + x, lc!() + next - &coefficients[i])?;
+            acc = lc!() + next;
+            acc_val = next_val;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    /// `1 + 2x + 3x^2` at `x = 5`.
+    fn circuit(public_coefficients: bool) -> HornerCircuit<BlsFr> {
+        HornerCircuit::new([1u64, 2, 3].map(BlsFr::from).to_vec(), BlsFr::from(5u64), public_coefficients, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(public_inputs(circuit(false)).unwrap(), vec![BlsFr::from(86u64)]);
+        assert_eq!(public_inputs(circuit(true)).unwrap(), [1u64, 2, 3, 86].map(BlsFr::from).to_vec());
+        assert!(is_satisfied(circuit(false)));
+        assert!(is_satisfied(circuit(true)));
+    }
+
+    #[test]
+    fn rejects_wrong_value_or_coefficient() {
+        assert!(!is_satisfied_after(circuit(false), |cs| cs.instance_assignment[1] += BlsFr::from(1u64)));
+        assert!(!is_satisfied_after(circuit(true), |cs| cs.instance_assignment[2] += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_wrong_accumulator() {
+        // Witnesses are `x`, then the accumulator `3x + 2`.
+        for public_coefficients in [false, true] {
+            for index in [0, 1] {
+                assert!(!is_satisfied_after(circuit(public_coefficients), |cs| {
+                    cs.witness_assignment[index] += BlsFr::from(1u64)
+                }));
+            }
+        }
+    }
+}
@@ -1,7 +1,28 @@
+mod circuit_params;
 mod circuit_traits;
 mod hadamard_circuit;
 mod addition_circuit;
 mod multiple_addition_circuit;
+mod vector_hadamard_circuit;
+mod matmul_circuit;
+mod mimc_circuit;
+mod poseidon_circuit;
+mod merkle_circuit;
+mod range_circuit;
+mod fibonacci_circuit;
+mod squaring_circuit;
+mod horner_circuit;
+mod sudoku_circuit;
+mod sorting_circuit;
+mod subset_sum_circuit;
+mod spmv_circuit;
+mod density_circuit;
+mod random_r1cs_circuit;
+mod public_inputs_circuit;
+mod circom_circuit;
+mod bristol_circuit;
+mod r1cs_export;
+mod witness;
 
 use std::time::Instant;
 use clap::{Parser, arg, command};
@@ -18,10 +39,29 @@ use marlin_v3::Marlin as MarlinV3;
 use marlin_v4::Marlin as MarlinV4;
 use new_inner::Marlin as MarlinNewInner;
 
-use circuit_traits::BenchCircuit;
+use circuit_params::CircuitParams;
+use circuit_traits::{iteration_instance, new_blank_instance, new_instance, or_exit, print_description, print_effective_size, print_spread, BenchCircuit};
 use addition_circuit::AdditionCircuit;
 use hadamard_circuit::HadamardCircuit;
 use multiple_addition_circuit::MultipleAdditionCircuit;
+use vector_hadamard_circuit::VectorHadamardCircuit;
+use matmul_circuit::MatMulCircuit;
+use mimc_circuit::MimcCircuit;
+use poseidon_circuit::PoseidonCircuit;
+use merkle_circuit::MerkleCircuit;
+use range_circuit::RangeCircuit;
+use fibonacci_circuit::FibonacciCircuit;
+use squaring_circuit::SquaringCircuit;
+use horner_circuit::HornerCircuit;
+use sudoku_circuit::SudokuCircuit;
+use sorting_circuit::SortingCircuit;
+use subset_sum_circuit::SubsetSumCircuit;
+use spmv_circuit::SpmvCircuit;
+use density_circuit::DensityCircuit;
+use random_r1cs_circuit::RandomR1csCircuit;
+use public_inputs_circuit::PublicInputsCircuit;
+use circom_circuit::CircomCircuit;
+use bristol_circuit::BristolCircuit;
 
 use ark_groth16::Groth16;
 use ark_snark::SNARK;
@@ -40,10 +80,30 @@ struct Args {
 
     #[arg(short, long, default_value = "false")]
     groth16: String,
+
+    /// Circuit specific options as key=value pairs separated by commas
+    #[arg(short, long, default_value = "")]
+    params: String,
+
+    /// Write the R1CS matrices to <prefix>.{a,b,c}.mtx and <prefix>.json instead of benchmarking
+    #[arg(long)]
+    export_r1cs: Option<String>,
+
+    /// Build the benchmarked circuit from the values in a JSON witness file instead of random ones
+    #[arg(long)]
+    witness: Option<String>,
+
+    /// Write the values of the benchmarked circuit to a JSON witness file, then benchmark exactly that circuit
+    #[arg(long)]
+    export_witness: Option<String>,
+
+    /// Prove a new random instance in every iteration instead of the same one, reusing the index
+    #[arg(long)]
+    fresh_witness: bool,
 }
 
 macro_rules! bench_1_2 {
-    ($marlin:ident, $version:expr, $circuit:ident, $constraints:expr) =>{
+    ($marlin:ident, $version:expr, $circuit:ident, $constraints:expr, $params:expr, $fresh:expr) =>{
         type MultiPC = MarlinKZG10<Bls12_381, DensePolynomial<BlsFr>>;
         type MarlinInst = $marlin::<BlsFr, MultiPC, Blake2s>;
 
@@ -54,31 +114,44 @@ macro_rules! bench_1_2 {
 
         let universal_srs = MarlinInst::universal_setup(num_constraints, num_variables, num_variables, rng).unwrap();
 
-        let circuit_r = $circuit::new_random(rng, $constraints, true);
+        let circuit_r = new_blank_instance::<BlsFr, $circuit<BlsFr>>($constraints, $params, true);
+        print_effective_size::<BlsFr, _>(&circuit_r);
 
         let (index_pk, index_vk) = MarlinInst::index(&universal_srs, circuit_r).unwrap();      
 
-        let circuit_instance = $circuit::new_random(rng, $constraints, false);
+        let circuit_instance = new_instance::<BlsFr, $circuit<BlsFr>, _>(rng, $constraints, $params, false);
+
+        let public_inputs = circuit_instance.get_public_inputs();
 
         let mut total_duration = std::time::Duration::new(0, 0);
+        let mut verify_duration = std::time::Duration::new(0, 0);
+        let mut prover_durations = Vec::new();
 
         for _ in 0..10 {
+            // Built before the timer starts, since `prove` takes the circuit by value.
+            let (circuit, public_inputs) = iteration_instance(&circuit_instance, &public_inputs, $fresh, rng, $constraints, $params);
             let start_time = Instant::now();
             
-            let proof = MarlinInst::prove(&index_pk, circuit_instance, rng).unwrap();
+            let proof = MarlinInst::prove(&index_pk, circuit, rng).unwrap();
 
-            assert!(MarlinInst::verify(&index_vk, &[circuit_instance.get_result()], &proof, rng).unwrap());
+            let verify_start_time = Instant::now();
+
+            assert!(MarlinInst::verify(&index_vk, &public_inputs, &proof, rng).unwrap());
 
             let end_time = Instant::now();
             let duration = end_time - start_time;
             total_duration += duration;
+            verify_duration += end_time - verify_start_time;
+            prover_durations.push(verify_start_time - start_time);
         }
         println!("\n{} {:?}", Colorize::bold(Colorize::cyan("Time spended proving and verifying in Marlin:")), total_duration/10);
+        println!("{} {:?}", Colorize::bold(Colorize::cyan("Time spended verifying in Marlin:")), verify_duration/10);
+        print_spread("Prover time in Marlin", $fresh, &prover_durations);
     }
 }
 
 macro_rules! bench_3_4 {
-    ($marlin:ident, $version:expr, $circuit:ident, $constraints:expr) =>{
+    ($marlin:ident, $version:expr, $circuit:ident, $constraints:expr, $params:expr, $fresh:expr) =>{
         
         type MultiPC = MarlinKZG10<Bls12_381, DensePolynomial<BlsFr>>;
         type MarlinInst = $marlin::<BlsFr, MultiPC, Blake2s>;
@@ -89,31 +162,44 @@ macro_rules! bench_3_4 {
         let rng = &mut ark_std::test_rng();
         let universal_srs = MarlinInst::universal_setup(num_constraints, num_variables, num_variables, rng).unwrap();
 
-        let circuit_r = $circuit::new_random(rng, $constraints, true);
+        let circuit_r = new_blank_instance::<BlsFr, $circuit<BlsFr>>($constraints, $params, true);
+        print_effective_size::<BlsFr, _>(&circuit_r);
 
         let (index_pk, index_vk) = MarlinInst::index(&universal_srs, circuit_r).unwrap();
         
-        let circuit_instance = $circuit::new_random(rng, $constraints, false);
+        let circuit_instance = new_instance::<BlsFr, $circuit<BlsFr>, _>(rng, $constraints, $params, false);
+
+        let public_inputs = circuit_instance.get_public_inputs();
 
         let mut total_duration = std::time::Duration::new(0, 0);
+        let mut verify_duration = std::time::Duration::new(0, 0);
+        let mut prover_durations = Vec::new();
 
         for _ in 0..10 {
+            // Built before the timer starts, since `prove` takes the circuit by value.
+            let (circuit, public_inputs) = iteration_instance(&circuit_instance, &public_inputs, $fresh, rng, $constraints, $params);
             let start_time = Instant::now();
             
-            let (proof, t_poly) = MarlinInst::prove(&index_pk, circuit_instance, rng).unwrap();
+            let (proof, t_poly) = MarlinInst::prove(&index_pk, circuit, rng).unwrap();
+
+            let verify_start_time = Instant::now();
 
-            assert!(MarlinInst::verify(&index_vk, &[circuit_instance.get_result()], &proof, rng, &t_poly).unwrap());
+            assert!(MarlinInst::verify(&index_vk, &public_inputs, &proof, rng, &t_poly).unwrap());
 
             let end_time = Instant::now();
             let duration = end_time - start_time;
             total_duration += duration;
+            verify_duration += end_time - verify_start_time;
+            prover_durations.push(verify_start_time - start_time);
         }
         println!("\n{} {:?}", Colorize::bold(Colorize::cyan("Time spended proving and verifying in Marlin:")), total_duration/10);
+        println!("{} {:?}", Colorize::bold(Colorize::cyan("Time spended verifying in Marlin:")), verify_duration/10);
+        print_spread("Prover time in Marlin", $fresh, &prover_durations);
     }
 }
 
 macro_rules! bench_5 {
-    ($marlin:ident, $version:expr, $circuit:ident, $constraints:expr) =>{
+    ($marlin:ident, $version:expr, $circuit:ident, $constraints:expr, $params:expr, $fresh:expr) =>{
         
         type MultiPC = MarlinKZG10<Bls12_381, DensePolynomial<BlsFr>>;
         type MarlinInst = $marlin::<BlsFr, MultiPC, Blake2s>;
@@ -124,13 +210,19 @@ macro_rules! bench_5 {
         let rng = &mut ark_std::test_rng();
         let universal_srs = MarlinInst::universal_setup(num_constraints, num_variables, num_variables, rng).unwrap();
 
-        let circuit_r = $circuit::new_random(rng, $constraints, true);
+        let circuit_r = new_blank_instance::<BlsFr, $circuit<BlsFr>>($constraints, $params, true);
+        print_effective_size::<BlsFr, _>(&circuit_r);
 
         let (index_pk, index_vk, matrix_a, matrix_b, matrix_c) = MarlinInst::index(&universal_srs, circuit_r).unwrap();
         
-        let circuit_instance = $circuit::new_random(rng, $constraints, false);
+        let circuit_instance = new_instance::<BlsFr, $circuit<BlsFr>, _>(rng, $constraints, $params, false);
+
+        let public_inputs = circuit_instance.get_public_inputs();
 
         let mut total_duration_acc = std::time::Duration::new(0, 0);
+        let mut verify_duration = std::time::Duration::new(0, 0);
+        let mut prover_durations = Vec::new();
+        let mut accumulation_durations = Vec::new();
 
         for _ in 0..5 {
 
@@ -140,9 +232,14 @@ macro_rules! bench_5 {
             let mut num_input_variables_acc = 0;
             let matrices = vec![matrix_a.clone(), matrix_b.clone(), matrix_c.clone()];
             for _i in 0..2{                  
-                let (proof, alpha, eta_a, eta_b, eta_c, beta, y, num_constraints_i, num_input_variables_i) = MarlinInst::prove(&index_pk, circuit_instance, rng).unwrap();
+                let (circuit, public_inputs) = iteration_instance(&circuit_instance, &public_inputs, $fresh, rng, $constraints, $params);
+                let prove_start_time = Instant::now();
+                let (proof, alpha, eta_a, eta_b, eta_c, beta, y, num_constraints_i, num_input_variables_i) = MarlinInst::prove(&index_pk, circuit, rng).unwrap();
+                prover_durations.push(prove_start_time.elapsed());
 
-                assert!(MarlinInst::verify(&index_vk, &[circuit_instance.get_result()], &proof, rng, &y).unwrap());
+                let verify_start_time = Instant::now();
+                assert!(MarlinInst::verify(&index_vk, &public_inputs, &proof, rng, &y).unwrap());
+                verify_duration += verify_start_time.elapsed();
 
                 let variables = vec![alpha, eta_a, eta_b, eta_c, beta, y];
                 all_variables.push(variables);
@@ -154,65 +251,169 @@ macro_rules! bench_5 {
             let end_time = Instant::now();
             let duration = end_time - start_time;
             total_duration_acc += duration;
+            accumulation_durations.push(duration);
             drop(matrices);
         }
         println!("\n{} {:?}", Colorize::bold(Colorize::cyan("Time spended doing the new inner part:")), total_duration_acc/10);
+        println!("{} {:?}", Colorize::bold(Colorize::cyan("Time spended verifying in Marlin:")), verify_duration/10);
+        print_spread("Prover time in Marlin", $fresh, &prover_durations);
+        print_spread("Accumulation time in the new inner part", $fresh, &accumulation_durations);
     }
 }
 
 macro_rules! bench_groth {
-    ($circuit:ident, $constraints:expr) => {
+    ($circuit:ident, $constraints:expr, $params:expr, $fresh:expr) => {
         let rng = &mut ark_std::test_rng();
         
-        let circuit_r = $circuit::new_random(rng, $constraints, true);
+        let circuit_r = new_blank_instance::<BlsFr, $circuit<BlsFr>>($constraints, $params, true);
+        print_effective_size::<BlsFr, _>(&circuit_r);
 
         let (index_pk, index_vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit_r, rng).unwrap();      
         
-        let circuit_instance = $circuit::new_random(rng, $constraints, false);
+        let circuit_instance = new_instance::<BlsFr, $circuit<BlsFr>, _>(rng, $constraints, $params, false);
+
+        let public_inputs = circuit_instance.get_public_inputs();
 
         let mut total_duration = std::time::Duration::new(0, 0);
+        let mut verify_duration = std::time::Duration::new(0, 0);
+        let mut prover_durations = Vec::new();
 
         for _ in 0..10 {
+            // Built before the timer starts, since `prove` takes the circuit by value.
+            let (circuit, public_inputs) = iteration_instance(&circuit_instance, &public_inputs, $fresh, rng, $constraints, $params);
             let start_time = Instant::now();
 
-            let proof = Groth16::<Bls12_381>::prove(&index_pk, circuit_instance, rng).unwrap();
+            let proof = Groth16::<Bls12_381>::prove(&index_pk, circuit, rng).unwrap();
+
+            let verify_start_time = Instant::now();
 
-            assert!(Groth16::<Bls12_381>::verify(&index_vk, &[circuit_instance.get_result()], &proof).unwrap());
+            assert!(Groth16::<Bls12_381>::verify(&index_vk, &public_inputs, &proof).unwrap());
 
             let end_time = Instant::now();
             let duration = end_time - start_time;
             total_duration += duration;
+            verify_duration += end_time - verify_start_time;
+            prover_durations.push(verify_start_time - start_time);
         }
         println!("\n{} {:?}", Colorize::bold(Colorize::cyan("Time spended proving and verifying in Groth16:")), total_duration/10);
+        println!("{} {:?}", Colorize::bold(Colorize::cyan("Time spended verifying in Groth16:")), verify_duration/10);
+        print_spread("Prover time in Groth16", $fresh, &prover_durations);
     }
 }
 
+macro_rules! bench_marlin {
+    ($circuit:ident, $version:expr, $constraints:expr, $params:expr, $fresh:expr) => {
+        match $version {
+            1 => {bench_1_2!(OriginalMarlin, $version, $circuit, $constraints, $params, $fresh);}
+            2 => {bench_1_2!(MarlinV2, $version, $circuit, $constraints, $params, $fresh);}
+            3 => {bench_3_4!(MarlinV3, $version, $circuit, $constraints, $params, $fresh);}
+            4 => {bench_3_4!(MarlinV4, $version, $circuit, $constraints, $params, $fresh);}
+            5 => {bench_5!(MarlinNewInner, $version, $circuit, $constraints, $params, $fresh);}
+            _ => println!("Invalid version"),
+        }
+    }
+}
+
+macro_rules! export_r1cs {
+    ($circuit:ident, $constraints:expr, $params:expr, $prefix:expr) => {
+        let circuit_r = new_blank_instance::<BlsFr, $circuit<BlsFr>>($constraints, $params, true);
+        print_effective_size::<BlsFr, _>(&circuit_r);
+
+        let matrices = r1cs_export::constraint_matrices::<BlsFr, _>(circuit_r).unwrap();
+
+        r1cs_export::write_matrix_market(&matrices, $prefix).unwrap();
+        r1cs_export::write_json(&matrices, &format!("{}.json", $prefix)).unwrap();
+        println!("\n{} {}.{{a,b,c}}.mtx, {}.json", Colorize::bold(Colorize::cyan("R1CS written to")), $prefix, $prefix);
+    }
+}
+
+macro_rules! export_witness {
+    ($circuit:ident, $constraints:expr, $params:expr, $path:expr) => {
+        let rng = &mut ark_std::test_rng();
+
+        let circuit = new_instance::<BlsFr, $circuit<BlsFr>, _>(rng, $constraints, $params, false);
+
+        circuit.witness().save($path).unwrap();
+        println!("{} {}\n", Colorize::bold(Colorize::cyan("Witness written to")), $path);
+    }
+}
+
+// Every circuit that can be selected with `--circuit`, by its `BenchCircuit::name`.
+macro_rules! with_circuit {
+    (all, $bench:ident!$args:tt) => {
+        with_circuit!(@types all, $bench, $args)
+    };
+    ($circuit:expr, $bench:ident!$args:tt) => {
+        with_circuit!(@types select, $circuit, $bench, $args)
+    };
+    (list) => {
+        with_circuit!(@types list)
+    };
+    (@types $($prefix:tt)*) => {
+        with_circuit!(@$($prefix)*, HadamardCircuit, AdditionCircuit, MultipleAdditionCircuit, VectorHadamardCircuit, MatMulCircuit, MimcCircuit, PoseidonCircuit, MerkleCircuit, RangeCircuit, FibonacciCircuit, SquaringCircuit, HornerCircuit, SudokuCircuit, SortingCircuit, SubsetSumCircuit, SpmvCircuit, DensityCircuit, RandomR1csCircuit, PublicInputsCircuit, CircomCircuit, BristolCircuit)
+    };
+    (@select, $circuit:expr, $bench:ident, $args:tt, $($ty:ident),*) => {
+        match $circuit {
+            $(name if name == $ty::<BlsFr>::name() => {call_bench!($bench, $ty, $args);})*
+            _ => println!("Invalid circuit"),
+        }
+    };
+    (@all, $bench:ident, $args:tt, $($ty:ident),*) => {
+        $(call_bench!($bench, $ty, $args);)*
+    };
+    (@list, $($ty:ident),*) => {
+        $(print_description::<BlsFr, $ty<BlsFr>>();)*
+    };
+}
+
+macro_rules! call_bench {
+    ($bench:ident, $circuit:ident, ($($arg:expr),*)) => {
+        $bench!($circuit, $($arg),*)
+    };
+}
+
 fn main() {
     let args = Args::parse();
     let version = args.version;
-    let circuit = args.circuit.as_str();
+    let mut params = or_exit(CircuitParams::parse(&args.params), "invalid --params");
+    let circuit = match args.circuit.split_once(':') {
+        Some((name, path)) => {
+            params.insert("path", path);
+            name
+        }
+        None => args.circuit.as_str(),
+    };
+    if let Some(path) = args.witness.as_deref() {
+        params.insert("witness", path);
+    }
     let constraints = args.constraints;
     let groth16 = args.groth16.as_str();
+    let fresh = args.fresh_witness;
+    // A witness file fixes the values, so there is nothing fresh to draw.
+    if fresh && (args.witness.is_some() || args.export_witness.is_some()) {
+        println!("--fresh-witness cannot be combined with --witness or --export-witness");
+        return;
+    }
     
-    let start_time = Instant::now();
-    match (version, circuit){
-        (1, "hadamard") => {bench_1_2!(OriginalMarlin, version, HadamardCircuit, constraints);}
-        (2, "hadamard") => {bench_1_2!(MarlinV2, version, HadamardCircuit, constraints);}
-        (3, "hadamard") => {bench_3_4!(MarlinV3, version, HadamardCircuit, constraints);}
-        (4, "hadamard") => {bench_3_4!(MarlinV4, version, HadamardCircuit, constraints);}
-        (5, "hadamard") => {bench_5!(MarlinNewInner, version, HadamardCircuit, constraints);}
-        (1, "addition") => {bench_1_2!(OriginalMarlin, version, AdditionCircuit, constraints);}
-        (2, "addition") => {bench_1_2!(MarlinV2, version, AdditionCircuit, constraints);}
-        (3, "addition") => {bench_3_4!(MarlinV3, version, AdditionCircuit, constraints);}
-        (4, "addition") => {bench_3_4!(MarlinV4, version, AdditionCircuit, constraints);}
-        (5, "addition") => {bench_5!(MarlinNewInner, version, AdditionCircuit, constraints);}
-        (1, "multiple_addition") => {bench_1_2!(OriginalMarlin, version, MultipleAdditionCircuit, constraints);}
-        (2, "multiple_addition") => {bench_1_2!(MarlinV2, version, MultipleAdditionCircuit, constraints);}
-        (3, "multiple_addition") => {bench_3_4!(MarlinV3, version, MultipleAdditionCircuit, constraints);}
-        (4, "multiple_addition") => {bench_3_4!(MarlinV4, version, MultipleAdditionCircuit, constraints);}
-        (5, "multiple_addition") => {bench_5!(MarlinNewInner, version, MultipleAdditionCircuit, constraints);}
-        _ => println!("Invalid version"),
+    if circuit == "list" {
+        with_circuit!(list);
+        return;
+    }
+
+    // The benchmarks then load the exported file, so the run can be repeated
+    // with `--witness`.
+    if let Some(path) = args.export_witness.as_deref() {
+        with_circuit!(circuit, export_witness!(constraints, &params, path));
+        params.insert("witness", path);
+    }
+
+    if let Some(prefix) = args.export_r1cs.as_deref() {
+        with_circuit!(circuit, export_r1cs!(constraints, &params, prefix));
+        return;
     }
+
+    let start_time = Instant::now();
+    with_circuit!(circuit, bench_marlin!(version, constraints, &params, fresh));
     let end_time = Instant::now();
     let duration = end_time - start_time;
     println!("{} {} {} {}{} {:?}", Colorize::bold(Colorize::cyan("Time spended in")), 
@@ -222,12 +423,7 @@ fn main() {
     if groth16 == "true"{
         println!("\n");
         let start_time = Instant::now();
-        match circuit{
-            "hadamard" => {bench_groth!(HadamardCircuit, constraints);}
-            "addition" => {bench_groth!(AdditionCircuit, constraints);}
-            "multiple_addition" => {bench_groth!(MultipleAdditionCircuit, constraints);}
-            _ => println!(""),
-        }
+        with_circuit!(circuit, bench_groth!(constraints, &params, fresh));
         let end_time = Instant::now();
         let duration = end_time - start_time;
         println!("{} {} {}{} {:?}", Colorize::bold(Colorize::cyan("Time spended in")), 
@@ -235,3 +431,197 @@ fn main() {
             Colorize::bold(Colorize::cyan(":")), duration);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    type MultiPC = MarlinKZG10<Bls12_381, DensePolynomial<BlsFr>>;
+
+    const SRS_SIZE: usize = 1 << 13;
+    const CONSTRAINTS: usize = 16;
+    /// Keeps the hash based circuits small enough for `SRS_SIZE`.
+    const PARAMS: &str = "rounds=5,full_rounds=2,partial_rounds=2,n=2,bits=8";
+
+    fn to_bytes<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        value.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// A blank circuit and two instances with different witnesses.
+    fn circuits<C: BenchCircuit<BlsFr>>() -> Vec<C> {
+        let params = CircuitParams::parse(PARAMS).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        vec![
+            C::new_blank(CONSTRAINTS, &params, false),
+            C::new_random(&mut rng, CONSTRAINTS, &params, false),
+            C::new_random(&mut rng, CONSTRAINTS, &params, false),
+        ]
+    }
+
+    // Indexes every circuit that needs no input file with `$index`, which
+    // returns the serialized verifier key, and checks the blank circuit gives
+    // the same key as both instances.
+    macro_rules! assert_same_index {
+        ($circuit:ident, $index:expr) => {
+            if $circuit::<BlsFr>::params().iter().all(|param| param.key != "path") {
+                let keys: Vec<Vec<u8>> = circuits::<$circuit<BlsFr>>().into_iter().map($index).collect();
+                assert!(keys.iter().all(|key| *key == keys[0]), "{}: index depends on the witness", $circuit::<BlsFr>::name());
+            }
+        };
+    }
+
+    // Proves the first instance against the index of the blank circuit with
+    // `$prove_and_verify`, which takes both circuits and the public inputs and
+    // reports whether the proof verifies.
+    macro_rules! assert_round_trip {
+        ($circuit:ident, $prove_and_verify:expr) => {
+            if $circuit::<BlsFr>::params().iter().all(|param| param.key != "path") {
+                let mut circuits = circuits::<$circuit<BlsFr>>().into_iter();
+                let (blank, instance) = (circuits.next().unwrap(), circuits.next().unwrap());
+                let inputs = instance.get_public_inputs();
+                assert!(($prove_and_verify)(blank, instance, inputs), "{}: proof does not verify", $circuit::<BlsFr>::name());
+            }
+        };
+    }
+
+    // Checks that `SRS_SIZE` leaves room for every circuit with `PARAMS`,
+    // counting the non-zeros of A and B together since the indexer may move
+    // entries between them.
+    macro_rules! assert_fits_srs {
+        ($circuit:ident,) => {
+            if $circuit::<BlsFr>::params().iter().all(|param| param.key != "path") {
+                let matrices = r1cs_export::constraint_matrices(circuits::<$circuit<BlsFr>>().remove(0)).unwrap();
+                let size = [
+                    matrices.num_constraints,
+                    matrices.num_instance_variables + matrices.num_witness_variables,
+                    matrices.a_num_non_zero + matrices.b_num_non_zero,
+                    matrices.c_num_non_zero,
+                ];
+                assert!(size.iter().all(|s| *s <= SRS_SIZE), "{}: {:?} exceeds the SRS", $circuit::<BlsFr>::name(), size);
+            }
+        };
+    }
+
+    #[test]
+    fn srs_fits_every_circuit() {
+        with_circuit!(all, assert_fits_srs!());
+    }
+
+    #[test]
+    fn marlin_v1_indexes_blank_circuits() {
+        type MarlinInst = OriginalMarlin<BlsFr, MultiPC, Blake2s>;
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, &mut ark_std::test_rng()).unwrap();
+        with_circuit!(all, assert_same_index!(|circuit| to_bytes(&MarlinInst::index(&srs, circuit).unwrap().1)));
+    }
+
+    #[test]
+    fn marlin_v2_indexes_blank_circuits() {
+        type MarlinInst = MarlinV2<BlsFr, MultiPC, Blake2s>;
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, &mut ark_std::test_rng()).unwrap();
+        with_circuit!(all, assert_same_index!(|circuit| to_bytes(&MarlinInst::index(&srs, circuit).unwrap().1)));
+    }
+
+    #[test]
+    fn marlin_v3_indexes_blank_circuits() {
+        type MarlinInst = MarlinV3<BlsFr, MultiPC, Blake2s>;
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, &mut ark_std::test_rng()).unwrap();
+        with_circuit!(all, assert_same_index!(|circuit| to_bytes(&MarlinInst::index(&srs, circuit).unwrap().1)));
+    }
+
+    #[test]
+    fn marlin_v4_indexes_blank_circuits() {
+        type MarlinInst = MarlinV4<BlsFr, MultiPC, Blake2s>;
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, &mut ark_std::test_rng()).unwrap();
+        with_circuit!(all, assert_same_index!(|circuit| to_bytes(&MarlinInst::index(&srs, circuit).unwrap().1)));
+    }
+
+    #[test]
+    fn new_inner_indexes_blank_circuits() {
+        type MarlinInst = MarlinNewInner<BlsFr, MultiPC, Blake2s>;
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, &mut ark_std::test_rng()).unwrap();
+        with_circuit!(all, assert_same_index!(|circuit| to_bytes(&MarlinInst::index(&srs, circuit).unwrap().1)));
+    }
+
+    #[test]
+    fn groth16_sets_up_blank_circuits() {
+        // Same setup randomness for every circuit, so only the shape can change the key.
+        with_circuit!(all, assert_same_index!(|circuit| {
+            let rng = &mut StdRng::seed_from_u64(0);
+            to_bytes(&Groth16::<Bls12_381>::circuit_specific_setup(circuit, rng).unwrap().1)
+        }));
+    }
+
+    #[test]
+    fn marlin_v1_proves_against_blank_index() {
+        type MarlinInst = OriginalMarlin<BlsFr, MultiPC, Blake2s>;
+        let rng = &mut ark_std::test_rng();
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, rng).unwrap();
+        with_circuit!(all, assert_round_trip!(|blank, instance, inputs: Vec<BlsFr>| {
+            let (index_pk, index_vk) = MarlinInst::index(&srs, blank).unwrap();
+            let proof = MarlinInst::prove(&index_pk, instance, rng).unwrap();
+            MarlinInst::verify(&index_vk, &inputs, &proof, rng).unwrap()
+        }));
+    }
+
+    #[test]
+    fn marlin_v2_proves_against_blank_index() {
+        type MarlinInst = MarlinV2<BlsFr, MultiPC, Blake2s>;
+        let rng = &mut ark_std::test_rng();
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, rng).unwrap();
+        with_circuit!(all, assert_round_trip!(|blank, instance, inputs: Vec<BlsFr>| {
+            let (index_pk, index_vk) = MarlinInst::index(&srs, blank).unwrap();
+            let proof = MarlinInst::prove(&index_pk, instance, rng).unwrap();
+            MarlinInst::verify(&index_vk, &inputs, &proof, rng).unwrap()
+        }));
+    }
+
+    #[test]
+    fn marlin_v3_proves_against_blank_index() {
+        type MarlinInst = MarlinV3<BlsFr, MultiPC, Blake2s>;
+        let rng = &mut ark_std::test_rng();
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, rng).unwrap();
+        with_circuit!(all, assert_round_trip!(|blank, instance, inputs: Vec<BlsFr>| {
+            let (index_pk, index_vk) = MarlinInst::index(&srs, blank).unwrap();
+            let (proof, t_poly) = MarlinInst::prove(&index_pk, instance, rng).unwrap();
+            MarlinInst::verify(&index_vk, &inputs, &proof, rng, &t_poly).unwrap()
+        }));
+    }
+
+    #[test]
+    fn marlin_v4_proves_against_blank_index() {
+        type MarlinInst = MarlinV4<BlsFr, MultiPC, Blake2s>;
+        let rng = &mut ark_std::test_rng();
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, rng).unwrap();
+        with_circuit!(all, assert_round_trip!(|blank, instance, inputs: Vec<BlsFr>| {
+            let (index_pk, index_vk) = MarlinInst::index(&srs, blank).unwrap();
+            let (proof, t_poly) = MarlinInst::prove(&index_pk, instance, rng).unwrap();
+            MarlinInst::verify(&index_vk, &inputs, &proof, rng, &t_poly).unwrap()
+        }));
+    }
+
+    #[test]
+    fn new_inner_proves_against_blank_index() {
+        type MarlinInst = MarlinNewInner<BlsFr, MultiPC, Blake2s>;
+        let rng = &mut ark_std::test_rng();
+        let srs = MarlinInst::universal_setup(SRS_SIZE, SRS_SIZE, SRS_SIZE, rng).unwrap();
+        with_circuit!(all, assert_round_trip!(|blank, instance, inputs: Vec<BlsFr>| {
+            let (index_pk, index_vk, ..) = MarlinInst::index(&srs, blank).unwrap();
+            let (proof, .., y, _, _) = MarlinInst::prove(&index_pk, instance, rng).unwrap();
+            MarlinInst::verify(&index_vk, &inputs, &proof, rng, &y).unwrap()
+        }));
+    }
+
+    #[test]
+    fn groth16_proves_against_blank_setup() {
+        let rng = &mut ark_std::test_rng();
+        with_circuit!(all, assert_round_trip!(|blank, instance, inputs: Vec<BlsFr>| {
+            let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(blank, rng).unwrap();
+            let proof = Groth16::<Bls12_381>::prove(&pk, instance, rng).unwrap();
+            Groth16::<Bls12_381>::verify(&vk, &inputs, &proof).unwrap()
+        }));
+    }
+}
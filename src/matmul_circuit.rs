@@ -0,0 +1,221 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{digest, digest_lc, print_stats, BenchCircuit, ParamSpec};
+use super::witness::{expect_len, invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+/// Proves `C = A × B` for two n×n witness matrices. Every `A_ik * B_kj` is
+/// its own constraint, and the last product of each entry absorbs the running
+/// sum so `C_ij` needs no extra constraint. `C` is public entry by entry, or
+/// only through its digest with `--params digest=true`.
+#[derive(Clone)]
+pub struct MatMulCircuit<F: Field> {
+    a: Vec<Vec<Option<F>>>,
+    b: Vec<Vec<Option<F>>>,
+    digest: bool,
+    print: bool,
+}
+
+impl<F: Field> MatMulCircuit<F> {
+    /// `a` and `b` are n×n matrices given as rows.
+    pub fn new(a: Vec<Vec<F>>, b: Vec<Vec<F>>, digest: bool, printing: bool) -> Self {
+        let n = a.len();
+        let square = |m: &[Vec<F>]| m.len() == n && m.iter().all(|row| row.len() == n);
+        assert!(n > 0 && square(&a) && square(&b), "matrices must be non-empty, square and of the same size");
+        let to_options = |m: Vec<Vec<F>>| m.into_iter().map(|row| row.into_iter().map(Some).collect()).collect();
+        MatMulCircuit {
+            a: to_options(a),
+            b: to_options(b),
+            digest,
+            print: printing,
+        }
+    }
+
+    /// Circuit of the same shape as `new` for n×n matrices, with no entries.
+    pub fn blank(n: usize, digest: bool, printing: bool) -> Self {
+        let blank = || vec![vec![None; n]; n];
+        MatMulCircuit { a: blank(), b: blank(), digest, print: printing }
+    }
+
+    /// Dimension from `--params`, as described in `new_random`.
+    fn params_dim(constraints: usize, params: &CircuitParams) -> usize {
+        let mut default_dim = 1;
+        while (default_dim + 1) * (default_dim + 1) * (default_dim + 1) <= constraints {
+            default_dim += 1;
+        }
+        params.get_or("dim", default_dim).max(1)
+    }
+
+    fn dim(&self) -> usize {
+        self.a.len()
+    }
+
+    /// Entries of `C` in row-major order.
+    fn product(&self) -> Vec<Option<F>> {
+        let n = self.dim();
+        let mut c = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n {
+                c.push((0..n).try_fold(F::zero(), |acc, k| Some(acc + self.a[i][k]? * self.b[k][j]?)));
+            }
+        }
+        c
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for MatMulCircuit<F> {
+    fn name() -> &'static str {
+        "matmul"
+    }
+
+    fn description() -> &'static str {
+        "Product of two n×n witness matrices"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "dim", default: "largest n with n³ <= constraints", description: "Matrix dimension" },
+            ParamSpec { key: "digest", default: "false", description: "Publish only the digest of the result" },
+        ]
+    }
+
+    /// The dimension is `--params dim=n`, by default the largest n with
+    /// n³ <= `--constraints`.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let n = Self::params_dim(constraints, params);
+        let mut random_matrix = || -> Vec<Vec<F>> {
+            (0..n).map(|_| (0..n).map(|_| <F>::rand(rng)).collect()).collect()
+        };
+        let a = random_matrix();
+        let b = random_matrix();
+        Self::new(a, b, params.get_or("digest", false), printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(Self::params_dim(constraints, params), params.get_or("digest", false), printing)
+    }
+
+    /// `a` and `b` are given in row-major order and their size replaces
+    /// `dim`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let a = witness.fields("a")?;
+        let b = witness.fields("b")?;
+        let n = (1..=a.len()).find(|n| n * n >= a.len()).unwrap_or(0);
+        if n == 0 || n * n != a.len() {
+            return Err(invalid(format!("`a` has {} entries, which is not a square", a.len())));
+        }
+        expect_len("b", &b, n * n)?;
+        let rows = |m: Vec<F>| m.chunks(n).map(|row| row.to_vec()).collect();
+        Ok(Self::new(rows(a), rows(b), params.get_or("digest", false), printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("a", self.a.iter().flatten().copied());
+        witness.set_fields("b", self.b.iter().flatten().copied());
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        let blank = |m: &[Vec<Option<F>>]| m.iter().map(|row| vec![None; row.len()]).collect();
+        MatMulCircuit { a: blank(&self.a), b: blank(&self.b), ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("dim", self.dim())]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for MatMulCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let n = self.dim();
+        let c_values = self.product();
+        let allocate_matrix = |m: &[Vec<Option<ConstraintF>>]| -> Result<Vec<Vec<Variable>>, SynthesisError> {
+            m.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|value| cs.new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing)))
+                        .collect()
+                })
+                .collect()
+        };
+        let a = allocate_matrix(&self.a)?;
+        let b = allocate_matrix(&self.b)?;
+
+        let mut c = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n {
+                let mut partial_sum = LinearCombination::zero();
+                for k in 0..(n - 1) {
+                    let product = cs.new_witness_variable(|| {
+                        Ok(self.a[i][k].ok_or(SynthesisError::AssignmentMissing)?
+                            * self.b[k][j].ok_or(SynthesisError::AssignmentMissing)?)
+                    })?;
+                    cs.enforce_constraint(lc!() + a[i][k], lc!() + b[k][j], lc!() + product)?;
+                    partial_sum += (ConstraintF::one(), product);
+                }
+                let value = || c_values[i * n + j].ok_or(SynthesisError::AssignmentMissing);
+                let c_ij = if self.digest { cs.new_witness_variable(value)? } else { cs.new_input_variable(value)? };
+                cs.enforce_constraint(lc!() + a[i][n - 1], lc!() + b[n - 1][j], lc!() + c_ij - &partial_sum)?;
+                c.push(c_ij);
+            }
+        }
+
+        if self.digest {
+            let d = cs.new_input_variable(|| digest(&c_values).ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(digest_lc(&c), lc!() + Variable::One, lc!() + d)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn matrix(rows: [[u64; 2]; 2]) -> Vec<Vec<BlsFr>> {
+        rows.iter().map(|row| row.iter().copied().map(BlsFr::from).collect()).collect()
+    }
+
+    fn circuit(digest: bool) -> MatMulCircuit<BlsFr> {
+        MatMulCircuit::new(matrix([[1, 2], [3, 4]]), matrix([[5, 6], [7, 8]]), digest, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(circuit(false).get_public_inputs(), [19u64, 22, 43, 50].map(BlsFr::from).to_vec());
+        assert!(is_satisfied(circuit(false)));
+        assert!(is_satisfied(circuit(true)));
+    }
+
+    #[test]
+    fn rejects_wrong_entry() {
+        for digest in [false, true] {
+            assert!(!is_satisfied_after(circuit(digest), |cs| cs.instance_assignment[1] += BlsFr::from(1u64)));
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_partial_product() {
+        // Witnesses start with `a` and `b`, then the partial product of
+        // `c_00` and, with a digest, `c_00` itself.
+        assert!(!is_satisfied_after(circuit(false), |cs| cs.witness_assignment[8] += BlsFr::from(1u64)));
+        for index in [8, 9] {
+            assert!(!is_satisfied_after(circuit(true), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::poseidon_circuit::{permute_gadget, PoseidonParams};
+use super::witness::{expect_len, invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+/// The compression function is one Poseidon permutation, by default with width
+/// 3, i.e. one sponge block holding the left and right children.
+const DEFAULT_WIDTH: usize = 3;
+const DEFAULT_FULL_ROUNDS: usize = 8;
+const DEFAULT_PARTIAL_ROUNDS: usize = 57;
+
+/// Proves that a witness leaf belongs to a Merkle tree of depth `depth` whose
+/// root is the public input. Each level takes the sibling and a direction bit
+/// (1 when the current node is the right child) as witnesses.
+#[derive(Clone)]
+pub struct MerkleCircuit<F: Field> {
+    leaf: Option<F>,
+    siblings: Vec<Option<F>>,
+    directions: Vec<Option<bool>>,
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    print: bool,
+}
+
+impl<F: Field> MerkleCircuit<F> {
+    /// `directions[i]` is true when the node at level `i` is the right child.
+    /// The children are compressed with a Poseidon permutation of the given
+    /// width, which must hold them next to the capacity element.
+    pub fn new(
+        leaf: F,
+        siblings: Vec<F>,
+        directions: Vec<bool>,
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+        printing: bool,
+    ) -> Self {
+        assert!(siblings.len() == directions.len(), "one sibling and direction per level");
+        let depth = siblings.len();
+        MerkleCircuit {
+            leaf: Some(leaf),
+            siblings: siblings.into_iter().map(Some).collect(),
+            directions: directions.into_iter().map(Some).collect(),
+            ..Self::blank(depth, width, full_rounds, partial_rounds, printing)
+        }
+    }
+
+    /// Circuit of the same shape as `new` for a tree of the given depth, with
+    /// no leaf, siblings or directions.
+    pub fn blank(depth: usize, width: usize, full_rounds: usize, partial_rounds: usize, printing: bool) -> Self {
+        assert!(depth > 0, "depth must be at least 1");
+        assert!(width >= 3, "width must be at least 3");
+        MerkleCircuit {
+            leaf: None,
+            siblings: vec![None; depth],
+            directions: vec![None; depth],
+            width,
+            full_rounds,
+            partial_rounds,
+            print: printing,
+        }
+    }
+
+    /// Width, full rounds and partial rounds of the compression function.
+    fn rounds(params: &CircuitParams) -> (usize, usize, usize) {
+        (
+            params.get_or("width", DEFAULT_WIDTH).max(3),
+            params.get_or("full_rounds", DEFAULT_FULL_ROUNDS),
+            params.get_or("partial_rounds", DEFAULT_PARTIAL_ROUNDS),
+        )
+    }
+
+    /// Depth, then the sizes from `rounds`, as described in `new_random`.
+    fn shape(constraints: usize, params: &CircuitParams) -> (usize, usize, usize, usize) {
+        let (width, full_rounds, partial_rounds) = Self::rounds(params);
+        let constraints_per_level = 3 * (width * full_rounds + partial_rounds) + 2;
+        let depth = params.get_or("depth", constraints / constraints_per_level).max(1);
+        (depth, width, full_rounds, partial_rounds)
+    }
+
+    fn poseidon(&self) -> PoseidonParams<F> {
+        PoseidonParams::new(self.width, self.full_rounds, self.partial_rounds)
+    }
+
+    #[cfg(test)]
+    fn root(&self) -> Option<F> {
+        let poseidon = self.poseidon();
+        self.siblings.iter().zip(&self.directions).try_fold(self.leaf?, |node, (sibling, right)| {
+            let sibling = (*sibling)?;
+            Some(if (*right)? { poseidon.hash(&[sibling, node]) } else { poseidon.hash(&[node, sibling]) })
+        })
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for MerkleCircuit<F> {
+    fn name() -> &'static str {
+        "merkle"
+    }
+
+    fn description() -> &'static str {
+        "Membership of a witness leaf in a Merkle tree with public root"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "depth", default: "fits --constraints", description: "Tree depth" },
+            ParamSpec { key: "width", default: "3", description: "State width t of the compression function" },
+            ParamSpec { key: "full_rounds", default: "8", description: "Full rounds R_F" },
+            ParamSpec { key: "partial_rounds", default: "57", description: "Partial rounds R_P" },
+        ]
+    }
+
+    /// `--params depth=d,width=t,full_rounds=RF,partial_rounds=RP`. By default
+    /// as many levels as fit in `--constraints`, with one Poseidon permutation
+    /// plus two constraints per level.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (depth, width, full_rounds, partial_rounds) = Self::shape(constraints, params);
+        let leaf = <F>::rand(rng);
+        let siblings = (0..depth).map(|_| <F>::rand(rng)).collect();
+        let directions = (0..depth).map(|_| rng.next_u32() & 1 == 1).collect();
+        Self::new(leaf, siblings, directions, width, full_rounds, partial_rounds, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (depth, width, full_rounds, partial_rounds) = Self::shape(constraints, params);
+        Self::blank(depth, width, full_rounds, partial_rounds, printing)
+    }
+
+    /// The number of siblings replaces `depth`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let siblings = witness.fields("siblings")?;
+        let directions = witness.bits("directions")?;
+        if siblings.is_empty() {
+            return Err(invalid("`siblings` is empty"));
+        }
+        expect_len("directions", &directions, siblings.len())?;
+        let (width, full_rounds, partial_rounds) = Self::rounds(params);
+        Ok(Self::new(witness.field("leaf")?, siblings, directions, width, full_rounds, partial_rounds, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("leaf", [self.leaf]);
+        witness.set_fields("siblings", self.siblings.iter().copied());
+        let directions: Vec<bool> = self.directions.iter().map(|d| d.expect("no value for `directions` to export")).collect();
+        witness.set_bits("directions", &directions);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        MerkleCircuit {
+            leaf: None,
+            siblings: vec![None; self.siblings.len()],
+            directions: vec![None; self.directions.len()],
+            ..self
+        }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("depth", self.siblings.len())]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for MerkleCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let poseidon = self.poseidon();
+        let leaf = cs.new_witness_variable(|| self.leaf.ok_or(SynthesisError::AssignmentMissing))?;
+        let mut node: LinearCombination<ConstraintF> = lc!() + leaf;
+        let mut node_val = self.leaf;
+
+        for (sibling_val, right_val) in self.siblings.iter().zip(&self.directions) {
+            let sibling = cs.new_witness_variable(|| sibling_val.ok_or(SynthesisError::AssignmentMissing))?;
+            let right = cs.new_witness_variable(|| {
+                right_val
+                    .map(|right| if right { ConstraintF::one() } else { ConstraintF::zero() })
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            cs.enforce_constraint(lc!() + right, lc!() + Variable::One - right, lc!())?;
+
+            // left = node + right * (sibling - node), and left + right = node + sibling.
+            let left_val = node_val.zip(*sibling_val).zip(*right_val).map(|((n, s), r)| if r { s } else { n });
+            let right_child_val = node_val.zip(*sibling_val).zip(*right_val).map(|((n, s), r)| if r { n } else { s });
+            let left = cs.new_witness_variable(|| left_val.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(lc!() + right, lc!() + sibling - &node, lc!() + left - &node)?;
+            let right_child = node + sibling - left;
+
+            let mut state = vec![lc!(); self.width];
+            let mut state_val = vec![Some(ConstraintF::zero()); self.width];
+            state[1] = lc!() + left;
+            state[2] = right_child;
+            state_val[1] = left_val;
+            state_val[2] = right_child_val;
+            permute_gadget(&cs, &poseidon, &mut state, &mut state_val)?;
+            node = state[1].clone();
+            node_val = state_val[1];
+        }
+
+        let root = cs.new_input_variable(|| node_val.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce_constraint(node, lc!() + Variable::One, lc!() + root)?;
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit(width: usize) -> MerkleCircuit<BlsFr> {
+        let siblings = [3u64, 5, 7].map(BlsFr::from).to_vec();
+        MerkleCircuit::new(BlsFr::from(1u64), siblings, vec![true, false, true], width, 4, 3, false)
+    }
+
+    #[test]
+    fn satisfied_and_matches_native_root() {
+        for width in [3, 4] {
+            assert_eq!(public_inputs(circuit(width)).unwrap(), vec![circuit(width).root().unwrap()]);
+            assert!(is_satisfied(circuit(width)));
+        }
+    }
+
+    #[test]
+    fn rejects_non_boolean_direction() {
+        // Witnesses start with the leaf, then the first sibling and direction.
+        assert!(!is_satisfied_after(circuit(3), |cs| cs.witness_assignment[2] = BlsFr::from(2u64)));
+    }
+
+    #[test]
+    fn rejects_wrong_path() {
+        // Index 1 is the first sibling, 2 its direction, 3 the left child and
+        // 4 the first S-box witness of the level's permutation.
+        assert!(!is_satisfied_after(circuit(3), |cs| cs.witness_assignment[1] += BlsFr::from(1u64)));
+        assert!(!is_satisfied_after(circuit(3), |cs| cs.witness_assignment[2] = BlsFr::from(0u64)));
+        assert!(!is_satisfied_after(circuit(3), |cs| cs.witness_assignment[3] += BlsFr::from(1u64)));
+        assert!(!is_satisfied_after(circuit(3), |cs| cs.witness_assignment[4] += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_wrong_root() {
+        assert!(!is_satisfied_after(circuit(3), |cs| cs.instance_assignment[1] += BlsFr::from(1u64)));
+    }
+}
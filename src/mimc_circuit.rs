@@ -0,0 +1,193 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::Witness;
+use ark_ff::{Field, PrimeField};
+
+/// x^5 is a permutation of the BLS12-381 scalar field (x^3 is not, since 3
+/// divides p - 1), and ceil(log_5(p)) = 110 rounds.
+const DEFAULT_ROUNDS: usize = 110;
+const CONSTANTS_SEED: u64 = 0x4d694d43;
+
+/// Round constants of the MiMC permutation. The first one is zero, the rest
+/// come from a fixed seed so the circuit and `mimc` always agree.
+pub fn mimc_constants<F: Field>(rounds: usize) -> Vec<F> {
+    let mut rng = StdRng::seed_from_u64(CONSTANTS_SEED);
+    (0..rounds).map(|i| if i == 0 { F::zero() } else { F::rand(&mut rng) }).collect()
+}
+
+/// MiMC-x^5 hash in Davies-Meyer mode: `H(x) = E(x) + x`, where `E` applies
+/// `s -> (s + c_i)^5` for every round constant.
+#[cfg(test)]
+pub fn mimc<F: Field>(x: F, constants: &[F]) -> F {
+    let mut s = x;
+    for c in constants {
+        let t = s + c;
+        s = t.square().square() * t;
+    }
+    s + x
+}
+
+/// Proves knowledge of `x` such that applying MiMC `chain` times to `x` gives
+/// the public digest.
+#[derive(Copy, Clone)]
+pub struct MimcCircuit<F: Field> {
+    preimage: Option<F>,
+    rounds: usize,
+    chain: usize,
+    print: bool,
+}
+
+impl<F: Field> MimcCircuit<F> {
+    pub fn new(preimage: F, rounds: usize, chain: usize, printing: bool) -> Self {
+        MimcCircuit { preimage: Some(preimage), ..Self::blank(rounds, chain, printing) }
+    }
+
+    /// Circuit of the same shape as `new` with no preimage.
+    pub fn blank(rounds: usize, chain: usize, printing: bool) -> Self {
+        MimcCircuit { preimage: None, rounds: rounds.max(1), chain: chain.max(1), print: printing }
+    }
+
+    /// Rounds and chain length from `--params`, by default as many hashes
+    /// as fit in `--constraints`.
+    fn size(constraints: usize, params: &CircuitParams) -> (usize, usize) {
+        let rounds = params.get_or("rounds", DEFAULT_ROUNDS).max(1);
+        let default_chain = (constraints / (3 * rounds + 1)).max(1);
+        (rounds, params.get_or("chain", default_chain))
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for MimcCircuit<F> {
+    fn name() -> &'static str {
+        "mimc"
+    }
+
+    fn description() -> &'static str {
+        "Chain of MiMC-x^5 hashes of a witness preimage"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "rounds", default: "110", description: "Rounds per hash" },
+            ParamSpec { key: "chain", default: "fits --constraints", description: "Number of chained hashes" },
+        ]
+    }
+
+    /// `--params rounds=R,chain=L`. By default the chain is as long as fits in
+    /// `--constraints`, with 3 constraints per round plus one per hash.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (rounds, chain) = Self::size(constraints, params);
+        Self::new(<F>::rand(rng), rounds, chain, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (rounds, chain) = Self::size(constraints, params);
+        Self::blank(rounds, chain, printing)
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let (rounds, chain) = Self::size(constraints, params);
+        Ok(Self::new(witness.field("preimage")?, rounds, chain, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("preimage", [self.preimage]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        MimcCircuit { preimage: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("rounds", self.rounds), ("chain", self.chain)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for MimcCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let constants = mimc_constants::<ConstraintF>(self.rounds);
+        let preimage = cs.new_witness_variable(|| self.preimage.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let mut h = preimage;
+        let mut h_val = self.preimage;
+        for link in 0..self.chain {
+            let mut s: LinearCombination<ConstraintF> = lc!() + h;
+            let mut s_val = h_val;
+            for c in &constants {
+                let t = s + (*c, Variable::One);
+                let t_val = s_val.map(|s| s + c);
+                let t2_val = t_val.map(|t| t.square());
+                let t4_val = t2_val.map(|t2| t2.square());
+                let next_val = t4_val.zip(t_val).map(|(t4, t)| t4 * t);
+
+                let t2 = cs.new_witness_variable(|| t2_val.ok_or(SynthesisError::AssignmentMissing))?;
+                let t4 = cs.new_witness_variable(|| t4_val.ok_or(SynthesisError::AssignmentMissing))?;
+                let next = cs.new_witness_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?;
+                cs.enforce_constraint(t.clone(), t.clone(), lc!() + t2)?;
+                cs.enforce_constraint(lc!() + t2, lc!() + t2, lc!() + t4)?;
+                cs.enforce_constraint(lc!() + t4, t, lc!() + next)?;
+
+                s = lc!() + next;
+                s_val = next_val;
+            }
+
+            // Davies-Meyer feed-forward. The last digest is the public input.
+            let out_val = s_val.zip(h_val).map(|(s, h)| s + h);
+            let out = if link + 1 == self.chain {
+                cs.new_input_variable(|| out_val.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.new_witness_variable(|| out_val.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce_constraint(s + h, lc!() + Variable::One, lc!() + out)?;
+            h = out;
+            h_val = out_val;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    #[test]
+    fn satisfied_and_matches_native_hash() {
+        let circuit = MimcCircuit::new(BlsFr::from(42u64), 5, 3, false);
+        let constants = mimc_constants(5);
+        let hash = (0..3).fold(BlsFr::from(42u64), |h, _| mimc(h, &constants));
+        assert_eq!(public_inputs(circuit).unwrap(), vec![hash]);
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn rejects_wrong_digest() {
+        let circuit = MimcCircuit::new(BlsFr::from(42u64), 5, 3, false);
+        assert!(!is_satisfied_after(circuit, |cs| cs.instance_assignment[1] += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_wrong_round_state() {
+        // Witnesses are the preimage, then `t^2, t^4, t^5` for each of the 5
+        // rounds, then the digest of the first link at index 16.
+        let circuit = MimcCircuit::new(BlsFr::from(42u64), 5, 3, false);
+        for index in [1, 2, 3, 15, 16] {
+            assert!(!is_satisfied_after(circuit, |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
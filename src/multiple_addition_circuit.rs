@@ -2,11 +2,13 @@ use ark_relations::{
     lc,
     r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
 };
-use colored::Colorize;
 use ark_std::rand::RngCore;
+use std::io;
 
-use super::circuit_traits::BenchCircuit;
-use ark_ff::Field;
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{padding, print_stats, surplus_padding, BenchCircuit, ParamSpec};
+use super::witness::Witness;
+use ark_ff::{Field, PrimeField};
 
 #[derive(Copy, Clone)]
 pub struct MultipleAdditionCircuit<F: Field> {
@@ -17,31 +19,107 @@ pub struct MultipleAdditionCircuit<F: Field> {
     s3: Option<F>,
     num_constraints: usize,
     num_variables: usize,
+    constrain_padding: bool,
     print: bool,
     
 }
 
-impl<F: Field> BenchCircuit<F> for MultipleAdditionCircuit<F> {
-    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, printing: bool) -> Self {
-        MultipleAdditionCircuit { 
-            a: Some(<F>::rand(rng)),  
-            b: Some(<F>::rand(rng)),  
-            s1: Some(<F>::rand(rng)), 
-            s2: Some(<F>::rand(rng)), 
-            s3: Some(<F>::rand(rng)),  
-            num_constraints: if constraints < 6 { 6 } else { constraints },
-            num_variables: if constraints < 6 { 6 } else { constraints },
-            print: printing,
+impl<F: Field> MultipleAdditionCircuit<F> {
+    /// `s` holds `s1`, `s2` and `s3`.
+    pub fn new(a: F, b: F, s: [F; 3], constraints: usize, variables: usize, constrain_padding: bool, printing: bool) -> Self {
+        MultipleAdditionCircuit {
+            a: Some(a),
+            b: Some(b),
+            s1: Some(s[0]),
+            s2: Some(s[1]),
+            s3: Some(s[2]),
+            ..Self::blank(constraints, variables, constrain_padding, printing)
         }
     }
 
-    fn get_result(&self) -> F {
-        if let (Some(a_val), Some(b_val), Some(s1_val), Some(s2_val), Some(s3_val)) = (self.a, self.b, self.s1, self.s2, self.s3) {
-            return (a_val + s1_val + s2_val) * (b_val + s3_val + s2_val) - s1_val - s2_val;
-        } else {
-            return <F>::zero();
+    /// Circuit of the same shape as `new` with no witness.
+    pub fn blank(constraints: usize, variables: usize, constrain_padding: bool, printing: bool) -> Self {
+        MultipleAdditionCircuit {
+            a: None,
+            b: None,
+            s1: None,
+            s2: None,
+            s3: None,
+            num_constraints: constraints.max(6),
+            num_variables: variables.max(6),
+            constrain_padding,
+            print: printing,
         }
     }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for MultipleAdditionCircuit<F> {
+    fn name() -> &'static str {
+        "multiple_addition"
+    }
+
+    fn description() -> &'static str {
+        "Repeats the constraint (a + s1 + s2) * (b + s3 + s2) = c + s1 + s2"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "variables", default: "constraints", description: "Number of variables, independent of --constraints" },
+            ParamSpec { key: "constrain_padding", default: "false", description: "Tie the padding variables to the existing constraints" },
+        ]
+    }
+
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::new(
+            <F>::rand(rng),
+            <F>::rand(rng),
+            [<F>::rand(rng), <F>::rand(rng), <F>::rand(rng)],
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        )
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        )
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        Ok(Self::new(
+            witness.field("a")?,
+            witness.field("b")?,
+            [witness.field("s1")?, witness.field("s2")?, witness.field("s3")?],
+            constraints,
+            params.get_or("variables", constraints),
+            params.get_or("constrain_padding", false),
+            printing,
+        ))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("a", [self.a]);
+        witness.set_fields("b", [self.b]);
+        witness.set_fields("s1", [self.s1]);
+        witness.set_fields("s2", [self.s2]);
+        witness.set_fields("s3", [self.s3]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        MultipleAdditionCircuit { a: None, b: None, s1: None, s2: None, s3: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        let surplus = surplus_padding(self.num_variables - 6, self.num_constraints, self.constrain_padding);
+        vec![("constraints", self.num_constraints + surplus), ("variables", self.num_variables)]
+    }
 } 
 
 impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for MultipleAdditionCircuit<ConstraintF> {
@@ -62,40 +140,13 @@ impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for MultipleAddition
             }
         })?;
 
-        for _ in 0..(self.num_variables - 6) { 
-            let _ = cs.new_witness_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
-        }
+        let padding = padding(&cs, self.num_variables - 6, (a, self.a), self.num_constraints, self.constrain_padding)?;
 
-        for _ in 0..(self.num_constraints) {
-            cs.enforce_constraint(lc!() + a + s1 + s2, lc!() + b + s3 +s2, lc!() + c + s1 + s2)?;
+        for term in &padding {
+            cs.enforce_constraint(lc!() + a + s1 + s2, lc!() + b + s3 +s2, lc!() + c + s1 + s2 + term)?;
         }
         if self.print == true{
-            println!("{} {}", Colorize::green("Constraints:"), cs.num_constraints());
-            println!("{} {}", Colorize::green("Variables:"), cs.num_constraints());
-            let matrices = cs.to_matrices().unwrap();
-            println!("{} {}", Colorize::green("Num witness variables:"), cs.num_witness_variables() + 1);
-            println!(
-                "{} A: {}, B: {}, C: {}", 
-                Colorize::blue("R1CS non-zeros -"),
-                matrices.a_num_non_zero,
-                matrices.b_num_non_zero,
-                matrices.c_num_non_zero,
-            );
-            let matrix_num_values = cs.num_constraints() * (cs.num_witness_variables() + 1);
-            println!(
-                "{} A: {}, B: {}, C: {}", 
-                Colorize::blue("R1CS zeros -"),
-                matrix_num_values - matrices.a_num_non_zero,
-                matrix_num_values - matrices.b_num_non_zero,
-                matrix_num_values - matrices.c_num_non_zero,
-            );
-            println!(
-                "{} A: {}%, B: {}%, C: {}%", 
-                Colorize::blue("R1CS sparsity -"),
-                ((matrix_num_values - matrices.a_num_non_zero) * 100) / matrix_num_values,
-                (matrix_num_values - matrices.b_num_non_zero) * 100 / matrix_num_values,
-                (matrix_num_values - matrices.c_num_non_zero) * 100 / matrix_num_values,
-            );
+            print_stats(&cs);
         }
         Ok(())
     }
@@ -0,0 +1,306 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::{invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+/// Usual parameters for x^5 over a 255-bit field with width 3.
+const DEFAULT_WIDTH: usize = 3;
+const DEFAULT_FULL_ROUNDS: usize = 8;
+const DEFAULT_PARTIAL_ROUNDS: usize = 57;
+const CONSTANTS_SEED: u64 = 0x506f736569646f6e;
+
+/// Round constants and MDS matrix of a Poseidon permutation with S-box x^5.
+pub struct PoseidonParams<F: Field> {
+    pub width: usize,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    pub round_constants: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+}
+
+impl<F: Field> PoseidonParams<F> {
+    /// The round constants come from a fixed seed and the MDS matrix is the
+    /// Cauchy matrix `1 / (x_i + y_j)` with `x_i = i` and `y_j = width + j`.
+    pub fn new(width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(CONSTANTS_SEED);
+        let round_constants = (0..(full_rounds + partial_rounds))
+            .map(|_| (0..width).map(|_| F::rand(&mut rng)).collect())
+            .collect();
+        let mds = (0..width)
+            .map(|i| (0..width).map(|j| F::from((i + width + j) as u64).inverse().unwrap()).collect())
+            .collect();
+        PoseidonParams { width, full_rounds, partial_rounds, round_constants, mds }
+    }
+
+    /// Rounds `0..full_rounds / 2` and the last `full_rounds / 2` apply the
+    /// S-box to the whole state, the rest only to its first element.
+    pub fn is_full_round(&self, round: usize) -> bool {
+        round < self.full_rounds / 2 || round >= self.full_rounds / 2 + self.partial_rounds
+    }
+
+    #[cfg(test)]
+    pub fn permute(&self, state: &mut [F]) {
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (s, c) in state.iter_mut().zip(constants) {
+                *s += c;
+            }
+            let sbox_len = if self.is_full_round(round) { self.width } else { 1 };
+            for s in state.iter_mut().take(sbox_len) {
+                *s = s.square().square() * *s;
+            }
+            let mixed: Vec<F> = self
+                .mds
+                .iter()
+                .map(|row| row.iter().zip(state.iter()).map(|(m, s)| *m * s).sum())
+                .collect();
+            state.copy_from_slice(&mixed);
+        }
+    }
+
+    /// Sponge with capacity 1 and rate `width - 1`. `message` must be a whole
+    /// number of blocks.
+    #[cfg(test)]
+    pub fn hash(&self, message: &[F]) -> F {
+        let mut state = vec![F::zero(); self.width];
+        for block in message.chunks(self.width - 1) {
+            for (s, m) in state[1..].iter_mut().zip(block) {
+                *s += m;
+            }
+            self.permute(&mut state);
+        }
+        state[1]
+    }
+}
+
+/// Proves knowledge of a message of `blocks` blocks whose Poseidon hash is the
+/// public input.
+#[derive(Clone)]
+pub struct PoseidonCircuit<F: Field> {
+    message: Vec<Option<F>>,
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    print: bool,
+}
+
+impl<F: Field> PoseidonCircuit<F> {
+    /// The message is absorbed in blocks of `width - 1` elements, so its
+    /// length must be a positive multiple of that.
+    pub fn new(message: Vec<F>, width: usize, full_rounds: usize, partial_rounds: usize, printing: bool) -> Self {
+        assert!(width >= 2, "width must be at least 2");
+        assert!(!message.is_empty() && message.len().is_multiple_of(width - 1), "message must be whole blocks of width - 1");
+        let blocks = message.len() / (width - 1);
+        PoseidonCircuit {
+            message: message.into_iter().map(Some).collect(),
+            ..Self::blank(blocks, width, full_rounds, partial_rounds, printing)
+        }
+    }
+
+    /// Circuit of the same shape as `new` for a message of `blocks` blocks,
+    /// with no message.
+    pub fn blank(blocks: usize, width: usize, full_rounds: usize, partial_rounds: usize, printing: bool) -> Self {
+        assert!(width >= 2, "width must be at least 2");
+        assert!(blocks > 0, "message must have at least one block");
+        PoseidonCircuit { message: vec![None; blocks * (width - 1)], width, full_rounds, partial_rounds, print: printing }
+    }
+
+    /// Blocks, width, full rounds and partial rounds, as described in
+    /// `new_random`.
+    fn shape(constraints: usize, params: &CircuitParams) -> (usize, usize, usize, usize) {
+        let width = params.get_or("width", DEFAULT_WIDTH).max(2);
+        let full_rounds = params.get_or("full_rounds", DEFAULT_FULL_ROUNDS);
+        let partial_rounds = params.get_or("partial_rounds", DEFAULT_PARTIAL_ROUNDS);
+        let constraints_per_block = (3 * (width * full_rounds + partial_rounds)).max(1);
+        let blocks = params.get_or("blocks", constraints / constraints_per_block).max(1);
+        (blocks, width, full_rounds, partial_rounds)
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for PoseidonCircuit<F> {
+    fn name() -> &'static str {
+        "poseidon"
+    }
+
+    fn description() -> &'static str {
+        "Poseidon sponge hash of a witness message"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "width", default: "3", description: "State width t" },
+            ParamSpec { key: "full_rounds", default: "8", description: "Full rounds R_F" },
+            ParamSpec { key: "partial_rounds", default: "57", description: "Partial rounds R_P" },
+            ParamSpec { key: "blocks", default: "fits --constraints", description: "Message blocks of t - 1 elements" },
+        ]
+    }
+
+    /// `--params width=t,full_rounds=RF,partial_rounds=RP,blocks=B`. By default
+    /// as many blocks as fit in `--constraints`, with 3 constraints per S-box.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (blocks, width, full_rounds, partial_rounds) = Self::shape(constraints, params);
+        let message = (0..(blocks * (width - 1))).map(|_| <F>::rand(rng)).collect();
+        Self::new(message, width, full_rounds, partial_rounds, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (blocks, width, full_rounds, partial_rounds) = Self::shape(constraints, params);
+        Self::blank(blocks, width, full_rounds, partial_rounds, printing)
+    }
+
+    /// The length of `message` replaces `blocks`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let width = params.get_or("width", DEFAULT_WIDTH).max(2);
+        let message = witness.fields("message")?;
+        if message.is_empty() || !message.len().is_multiple_of(width - 1) {
+            return Err(invalid(format!("`message` must be whole blocks of {} elements", width - 1)));
+        }
+        let full_rounds = params.get_or("full_rounds", DEFAULT_FULL_ROUNDS);
+        let partial_rounds = params.get_or("partial_rounds", DEFAULT_PARTIAL_ROUNDS);
+        Ok(Self::new(message, width, full_rounds, partial_rounds, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("message", self.message.iter().copied());
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        PoseidonCircuit { message: vec![None; self.message.len()], ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("width", self.width),
+            ("full_rounds", self.full_rounds),
+            ("partial_rounds", self.partial_rounds),
+            ("blocks", self.message.len() / (self.width - 1)),
+        ]
+    }
+}
+
+/// Allocates `x^5` for the linear combination `x` with three constraints.
+fn pow5<F: Field>(
+    cs: &ConstraintSystemRef<F>,
+    x: LinearCombination<F>,
+    x_val: Option<F>,
+) -> Result<Variable, SynthesisError> {
+    let x2_val = x_val.map(|x| x.square());
+    let x4_val = x2_val.map(|x2| x2.square());
+    let x2 = cs.new_witness_variable(|| x2_val.ok_or(SynthesisError::AssignmentMissing))?;
+    let x4 = cs.new_witness_variable(|| x4_val.ok_or(SynthesisError::AssignmentMissing))?;
+    let x5 = cs.new_witness_variable(|| {
+        Ok(x4_val.ok_or(SynthesisError::AssignmentMissing)? * x_val.ok_or(SynthesisError::AssignmentMissing)?)
+    })?;
+    cs.enforce_constraint(x.clone(), x.clone(), lc!() + x2)?;
+    cs.enforce_constraint(lc!() + x2, lc!() + x2, lc!() + x4)?;
+    cs.enforce_constraint(lc!() + x4, x, lc!() + x5)?;
+    Ok(x5)
+}
+
+/// In-circuit version of `PoseidonParams::permute` over linear combinations,
+/// keeping the native values of the state alongside.
+pub fn permute_gadget<F: Field>(
+    cs: &ConstraintSystemRef<F>,
+    poseidon: &PoseidonParams<F>,
+    state: &mut [LinearCombination<F>],
+    state_val: &mut [Option<F>],
+) -> Result<(), SynthesisError> {
+    for (round, constants) in poseidon.round_constants.iter().enumerate() {
+        for ((s, s_val), c) in state.iter_mut().zip(state_val.iter_mut()).zip(constants) {
+            *s += (*c, Variable::One);
+            *s_val = s_val.map(|s| s + c);
+        }
+        let sbox_len = if poseidon.is_full_round(round) { poseidon.width } else { 1 };
+        for (s, s_val) in state.iter_mut().zip(state_val.iter_mut()).take(sbox_len) {
+            let x5 = pow5(cs, s.clone(), *s_val)?;
+            *s = lc!() + x5;
+            *s_val = s_val.map(|x| x.square().square() * x);
+        }
+        let mixed: Vec<LinearCombination<F>> = poseidon
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(state.iter()).fold(lc!(), |acc, (m, s)| acc + (*m, s)))
+            .collect();
+        let mixed_val: Vec<Option<F>> = poseidon
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(state_val.iter()).try_fold(F::zero(), |acc, (m, s)| Some(acc + *m * (*s)?)))
+            .collect();
+        state.clone_from_slice(&mixed);
+        state_val.copy_from_slice(&mixed_val);
+    }
+    Ok(())
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for PoseidonCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let poseidon = PoseidonParams::<ConstraintF>::new(self.width, self.full_rounds, self.partial_rounds);
+        let mut state: Vec<LinearCombination<ConstraintF>> = vec![lc!(); self.width];
+        let mut state_val: Vec<Option<ConstraintF>> = vec![Some(ConstraintF::zero()); self.width];
+
+        for block in self.message.chunks(self.width - 1) {
+            for ((s, s_val), m_val) in state[1..].iter_mut().zip(&mut state_val[1..]).zip(block) {
+                let m = cs.new_witness_variable(|| m_val.ok_or(SynthesisError::AssignmentMissing))?;
+                *s += (ConstraintF::one(), m);
+                *s_val = s_val.zip(*m_val).map(|(s, m)| s + m);
+            }
+
+            permute_gadget(&cs, &poseidon, &mut state, &mut state_val)?;
+        }
+
+        let output = cs.new_input_variable(|| state_val[1].ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce_constraint(state[1].clone(), lc!() + Variable::One, lc!() + output)?;
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit() -> PoseidonCircuit<BlsFr> {
+        let message = (1..=4u64).map(BlsFr::from).collect();
+        PoseidonCircuit::new(message, 3, 4, 3, false)
+    }
+
+    #[test]
+    fn satisfied_and_matches_native_hash() {
+        let message: Vec<BlsFr> = (1..=4u64).map(BlsFr::from).collect();
+        let hash = PoseidonParams::new(3, 4, 3).hash(&message);
+        assert_eq!(public_inputs(circuit()).unwrap(), vec![hash]);
+        assert!(is_satisfied(circuit()));
+    }
+
+    #[test]
+    fn rejects_wrong_sbox_or_digest() {
+        assert!(!is_satisfied_after(circuit(), |cs| cs.witness_assignment[4] += BlsFr::from(1u64)));
+        assert!(!is_satisfied_after(circuit(), |cs| cs.instance_assignment[1] += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_wrong_round_state() {
+        // The first block's two message elements come first, then three
+        // witnesses per S-box: 9 for each full round and 3 for each partial
+        // one. Index 22 is the first partial round's `x^5` and index 47 the
+        // second block's first message element.
+        for index in [0, 10, 22, 47] {
+            assert!(!is_satisfied_after(circuit(), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::{invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+/// `--constraints` constraints and any number of public inputs `x_i`, so the
+/// verifier's cost can be measured against the public inputs alone. Constraint
+/// j is `(sum_{i = j mod m} x_i) * a = p_j` with a witness `a`, so every public
+/// input appears exactly once and the number of constraints does not change.
+#[derive(Clone)]
+pub struct PublicInputsCircuit<F: Field> {
+    inputs: Vec<F>,
+    a: Option<F>,
+    num_constraints: usize,
+    print: bool,
+}
+
+impl<F: Field> PublicInputsCircuit<F> {
+    pub fn new(inputs: Vec<F>, a: F, constraints: usize, printing: bool) -> Self {
+        assert!(!inputs.is_empty(), "at least one public input");
+        PublicInputsCircuit { inputs, a: Some(a), num_constraints: constraints.max(1), print: printing }
+    }
+
+    /// Circuit of the same shape as `new` with `public` inputs. They are only
+    /// values of the instance, so they are left at zero, and `a` is unset.
+    pub fn blank(public: usize, constraints: usize, printing: bool) -> Self {
+        PublicInputsCircuit { a: None, ..Self::new(vec![F::zero(); public.max(1)], F::zero(), constraints, printing) }
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for PublicInputsCircuit<F> {
+    fn name() -> &'static str {
+        "public_inputs"
+    }
+
+    fn description() -> &'static str {
+        "Fixed number of constraints with a configurable number of public inputs"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "public", default: "1", description: "Number of public inputs" },
+        ]
+    }
+
+    /// The number of public inputs is `--params public=n`, 1 by default.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let public = params.get_or("public", 1).max(1);
+        let inputs = (0..public).map(|_| <F>::rand(rng)).collect();
+        Self::new(inputs, <F>::rand(rng), constraints, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(params.get_or("public", 1), constraints, printing)
+    }
+
+    /// The number of inputs replaces `public`.
+    fn from_witness(witness: &Witness, constraints: usize, _params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let inputs = witness.fields("inputs")?;
+        if inputs.is_empty() {
+            return Err(invalid("`inputs` is empty"));
+        }
+        Ok(Self::new(inputs, witness.field("a")?, constraints, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("inputs", self.inputs.iter().copied().map(Some));
+        witness.set_fields("a", [self.a]);
+        witness
+    }
+
+    /// The public inputs stay, since they are not witness values.
+    fn into_blank(self) -> Self {
+        PublicInputsCircuit { a: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("constraints", self.num_constraints), ("public", self.inputs.len())]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for PublicInputsCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let mut sums = vec![LinearCombination::zero(); self.num_constraints];
+        let mut sum_values = vec![ConstraintF::zero(); self.num_constraints];
+        for (i, x_val) in self.inputs.iter().enumerate() {
+            let x = cs.new_input_variable(|| Ok(*x_val))?;
+            sums[i % self.num_constraints] += (ConstraintF::one(), x);
+            sum_values[i % self.num_constraints] += x_val;
+        }
+        let a = cs.new_witness_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
+
+        for (sum, sum_val) in sums.into_iter().zip(sum_values) {
+            let p = cs.new_witness_variable(|| Ok(sum_val * self.a.ok_or(SynthesisError::AssignmentMissing)?))?;
+            cs.enforce_constraint(sum, lc!() + a, lc!() + p)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
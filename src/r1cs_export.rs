@@ -0,0 +1,337 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use ark_ff::{FpParameters, PrimeField};
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, Matrix, OptimizationGoal,
+    SynthesisError, SynthesisMode,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use super::witness::invalid;
+
+/// Triplet representation of the R1CS written by `write_json`. Columns
+/// `0..num_instance_variables` are the instance (including the constant one)
+/// and the remaining `num_witness_variables` columns are the witness.
+#[derive(Serialize, Deserialize)]
+struct JsonR1cs {
+    modulus: String,
+    num_constraints: usize,
+    num_instance_variables: usize,
+    num_witness_variables: usize,
+    a: Vec<(usize, usize, String)>,
+    b: Vec<(usize, usize, String)>,
+    c: Vec<(usize, usize, String)>,
+}
+
+/// Synthesizes `circuit` in setup mode, with the same optimization goal as the
+/// Marlin indexer, and returns its A, B and C matrices. These are the circuit's
+/// own matrices: the indexer additionally pads them to a square matrix and
+/// balances the non-zeros between A and B, which is not applied here.
+pub fn constraint_matrices<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<ConstraintMatrices<F>, SynthesisError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Weight);
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone())?;
+    cs.finalize();
+    cs.to_matrices().ok_or(SynthesisError::AssignmentMissing)
+}
+
+/// Writes `<prefix>.a.mtx`, `<prefix>.b.mtx` and `<prefix>.c.mtx` in
+/// MatrixMarket coordinate format (1-based indices).
+pub fn write_matrix_market<F: PrimeField>(matrices: &ConstraintMatrices<F>, prefix: &str) -> io::Result<()> {
+    for (name, matrix, num_non_zero) in [
+        ("a", &matrices.a, matrices.a_num_non_zero),
+        ("b", &matrices.b, matrices.b_num_non_zero),
+        ("c", &matrices.c, matrices.c_num_non_zero),
+    ] {
+        let mut file = BufWriter::new(File::create(format!("{}.{}.mtx", prefix, name))?);
+        writeln!(file, "%%MatrixMarket matrix coordinate integer general")?;
+        writeln!(file, "% modulus: {}", modulus::<F>())?;
+        writeln!(file, "% instance variables: {}", matrices.num_instance_variables)?;
+        writeln!(file, "% witness variables: {}", matrices.num_witness_variables)?;
+        writeln!(
+            file,
+            "{} {} {}",
+            matrices.num_constraints,
+            matrices.num_instance_variables + matrices.num_witness_variables,
+            num_non_zero,
+        )?;
+        for (row, entries) in matrix.iter().enumerate() {
+            for (value, column) in entries {
+                writeln!(file, "{} {} {}", row + 1, column + 1, to_signed_string(value))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads back the three files written by `write_matrix_market`.
+#[allow(dead_code)]
+pub fn read_matrix_market<F: PrimeField>(prefix: &str) -> io::Result<ConstraintMatrices<F>> {
+    let mut matrices = Vec::new();
+    let mut shape = None;
+    for name in ["a", "b", "c"] {
+        let file = BufReader::new(File::open(format!("{}.{}.mtx", prefix, name))?);
+        let mut num_instance_variables = None;
+        let mut num_witness_variables = None;
+        let mut size = None;
+        let mut matrix: Matrix<F> = Vec::new();
+        for line in file.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("%%") {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('%') {
+                if let Some((key, value)) = comment.split_once(':') {
+                    match key.trim() {
+                        "modulus" => check_modulus::<F>(value.trim())?,
+                        "instance variables" => num_instance_variables = Some(parse_usize(value)?),
+                        "witness variables" => num_witness_variables = Some(parse_usize(value)?),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(invalid(format!("malformed MatrixMarket line `{}`", line)));
+            }
+            if size.is_none() {
+                let rows = parse_usize(fields[0])?;
+                size = Some((rows, parse_usize(fields[1])?));
+                matrix = vec![Vec::new(); rows];
+                continue;
+            }
+            let row = parse_usize(fields[0])?;
+            let column = parse_usize(fields[1])?;
+            let columns = size.unwrap().1;
+            if row == 0 || row > matrix.len() || column == 0 || column > columns {
+                return Err(invalid(format!("entry ({}, {}) out of range", row, column)));
+            }
+            matrix[row - 1].push((from_signed_string(fields[2])?, column - 1));
+        }
+        let (num_instance_variables, num_witness_variables) = match (num_instance_variables, num_witness_variables) {
+            (Some(instance), Some(witness)) => (instance, witness),
+            _ => return Err(invalid("missing instance/witness column split")),
+        };
+        let (rows, columns) = size.ok_or_else(|| invalid("missing MatrixMarket size line"))?;
+        if columns != num_instance_variables + num_witness_variables {
+            return Err(invalid("column count does not match the instance/witness split"));
+        }
+        if shape.get_or_insert((rows, num_instance_variables, num_witness_variables))
+            != &(rows, num_instance_variables, num_witness_variables)
+        {
+            return Err(invalid("A, B and C have different shapes"));
+        }
+        matrices.push(matrix);
+    }
+    let (num_constraints, num_instance_variables, num_witness_variables) = shape.unwrap();
+    let c = matrices.pop().unwrap();
+    let b = matrices.pop().unwrap();
+    let a = matrices.pop().unwrap();
+    Ok(ConstraintMatrices {
+        num_instance_variables,
+        num_witness_variables,
+        num_constraints,
+        a_num_non_zero: num_non_zero(&a),
+        b_num_non_zero: num_non_zero(&b),
+        c_num_non_zero: num_non_zero(&c),
+        a,
+        b,
+        c,
+    })
+}
+
+/// Writes the three matrices as `(row, column, value)` triplets (0-based) in a
+/// single JSON file.
+pub fn write_json<F: PrimeField>(matrices: &ConstraintMatrices<F>, path: &str) -> io::Result<()> {
+    let json = JsonR1cs {
+        modulus: modulus::<F>(),
+        num_constraints: matrices.num_constraints,
+        num_instance_variables: matrices.num_instance_variables,
+        num_witness_variables: matrices.num_witness_variables,
+        a: to_triplets(&matrices.a),
+        b: to_triplets(&matrices.b),
+        c: to_triplets(&matrices.c),
+    };
+    let file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(file, &json).map_err(io::Error::from)
+}
+
+/// Reads back a file written by `write_json`.
+#[allow(dead_code)]
+pub fn read_json<F: PrimeField>(path: &str) -> io::Result<ConstraintMatrices<F>> {
+    let file = BufReader::new(File::open(path)?);
+    let json: JsonR1cs = serde_json::from_reader(file).map_err(io::Error::from)?;
+    check_modulus::<F>(&json.modulus)?;
+    let num_columns = json.num_instance_variables + json.num_witness_variables;
+    let a = from_triplets(&json.a, json.num_constraints, num_columns)?;
+    let b = from_triplets(&json.b, json.num_constraints, num_columns)?;
+    let c = from_triplets(&json.c, json.num_constraints, num_columns)?;
+    Ok(ConstraintMatrices {
+        num_instance_variables: json.num_instance_variables,
+        num_witness_variables: json.num_witness_variables,
+        num_constraints: json.num_constraints,
+        a_num_non_zero: num_non_zero(&a),
+        b_num_non_zero: num_non_zero(&b),
+        c_num_non_zero: num_non_zero(&c),
+        a,
+        b,
+        c,
+    })
+}
+
+fn to_triplets<F: PrimeField>(matrix: &Matrix<F>) -> Vec<(usize, usize, String)> {
+    matrix
+        .iter()
+        .enumerate()
+        .flat_map(|(row, entries)| {
+            entries.iter().map(move |(value, column)| (row, *column, to_signed_string(value)))
+        })
+        .collect()
+}
+
+fn from_triplets<F: PrimeField>(
+    triplets: &[(usize, usize, String)],
+    num_rows: usize,
+    num_columns: usize,
+) -> io::Result<Matrix<F>> {
+    let mut matrix = vec![Vec::new(); num_rows];
+    for (row, column, value) in triplets {
+        if *row >= num_rows || *column >= num_columns {
+            return Err(invalid(format!("entry ({}, {}) out of range", row, column)));
+        }
+        matrix[*row].push((from_signed_string(value)?, *column));
+    }
+    Ok(matrix)
+}
+
+fn num_non_zero<F: PrimeField>(matrix: &Matrix<F>) -> usize {
+    matrix.iter().map(|row| row.len()).sum()
+}
+
+fn modulus<F: PrimeField>() -> String {
+    let modulus: BigUint = F::Params::MODULUS.into();
+    modulus.to_string()
+}
+
+fn check_modulus<F: PrimeField>(value: &str) -> io::Result<()> {
+    if value == modulus::<F>() {
+        Ok(())
+    } else {
+        Err(invalid(format!("field modulus {} does not match {}", value, modulus::<F>())))
+    }
+}
+
+/// Field elements above `(p - 1) / 2` are written as negative integers so that
+/// small coefficients such as `-1` stay readable in external tools.
+fn to_signed_string<F: PrimeField>(value: &F) -> String {
+    let positive: BigUint = (*value).into();
+    let negative: BigUint = (-*value).into();
+    if negative < positive {
+        format!("-{}", negative)
+    } else {
+        positive.to_string()
+    }
+}
+
+/// Inverse of `to_signed_string`. Magnitudes of at least `p` are rejected
+/// rather than reduced, so every element has a single encoding.
+fn from_signed_string<F: PrimeField>(value: &str) -> io::Result<F> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let magnitude = BigUint::parse_bytes(digits.as_bytes(), 10)
+        .ok_or_else(|| invalid(format!("invalid field element `{}`", value)))?;
+    if magnitude >= F::Params::MODULUS.into() {
+        return Err(invalid(format!("field element `{}` is not reduced modulo {}", value, modulus::<F>())));
+    }
+    let element = F::from(magnitude);
+    Ok(if negative { -element } else { element })
+}
+
+fn parse_usize(value: &str) -> io::Result<usize> {
+    value.trim().parse().map_err(|_| invalid(format!("invalid integer `{}`", value.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_params::CircuitParams;
+    use crate::circuit_traits::BenchCircuit;
+    use crate::multiple_addition_circuit::MultipleAdditionCircuit;
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn sample_matrices() -> ConstraintMatrices<BlsFr> {
+        let rng = &mut ark_std::test_rng();
+        let circuit = MultipleAdditionCircuit::<BlsFr>::new_random(rng, 8, &CircuitParams::default(), false);
+        constraint_matrices(circuit).unwrap()
+    }
+
+    /// Temporary file name unique to this test process, so that concurrent
+    /// runs of the test suite do not overwrite each other's files.
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("marlin_r1cs_export_{}_{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn remove_matrix_market(prefix: &str) {
+        for name in ["a", "b", "c"] {
+            std::fs::remove_file(format!("{}.{}.mtx", prefix, name)).unwrap();
+        }
+    }
+
+    #[test]
+    fn matrix_market_round_trip() {
+        let matrices = sample_matrices();
+        let prefix = temp_path("matrix_market_round_trip");
+        write_matrix_market(&matrices, &prefix).unwrap();
+        assert_eq!(read_matrix_market::<BlsFr>(&prefix).unwrap(), matrices);
+        remove_matrix_market(&prefix);
+    }
+
+    #[test]
+    fn rejects_column_out_of_range() {
+        let matrices = sample_matrices();
+        let prefix = temp_path("rejects_column_out_of_range");
+        write_matrix_market(&matrices, &prefix).unwrap();
+        let columns = matrices.num_instance_variables + matrices.num_witness_variables;
+        let mut file = std::fs::OpenOptions::new().append(true).open(format!("{}.a.mtx", prefix)).unwrap();
+        writeln!(file, "1 {} 1", columns + 1).unwrap();
+        assert!(read_matrix_market::<BlsFr>(&prefix).is_err());
+        remove_matrix_market(&prefix);
+    }
+
+    #[test]
+    fn rejects_unreduced_element() {
+        let p = modulus::<BlsFr>();
+        assert!(from_signed_string::<BlsFr>(&p).is_err());
+        assert!(from_signed_string::<BlsFr>(&format!("-{}", p)).is_err());
+        let p_minus_one = (BigUint::parse_bytes(p.as_bytes(), 10).unwrap() - 1u32).to_string();
+        assert_eq!(from_signed_string::<BlsFr>(&p_minus_one).unwrap(), -BlsFr::from(1u64));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let matrices = sample_matrices();
+        let path = temp_path("json_round_trip.json");
+        write_json(&matrices, &path).unwrap();
+        assert_eq!(read_json::<BlsFr>(&path).unwrap(), matrices);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_field() {
+        let matrices = sample_matrices();
+        let path = temp_path("rejects_other_field.json");
+        write_json(&matrices, &path).unwrap();
+        assert!(read_json::<ark_bls12_381::Fq>(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use std::io;
+use std::ops::RangeInclusive;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{BenchCircuit, ParamSpec};
+use super::density_circuit::DensityCircuit;
+use super::witness::{expect_len, Witness};
+use ark_ff::{Field, PrimeField};
+
+const DEFAULT_SEED: u64 = 0;
+const MAX_PUBLIC: usize = 16;
+const MAX_NONZEROS: usize = 4;
+
+/// Random satisfiable R1CS: a `DensityCircuit` whose size, number of public
+/// inputs, non-zeros per row and coefficients all come from `--params seed=s`.
+/// The witness comes from the benchmark's rng, so every instance of a seed
+/// shares the index.
+#[derive(Clone)]
+pub struct RandomR1csCircuit<F: Field>(DensityCircuit<F>);
+
+/// Constraints, variables, public inputs, non-zeros per row and shape seed
+/// drawn from the seed.
+type Shape = (usize, usize, usize, [RangeInclusive<usize>; 3], u64);
+
+impl<F: Field> RandomR1csCircuit<F> {
+    fn shape(constraints: usize, params: &CircuitParams) -> Shape {
+        let mut seeded = StdRng::seed_from_u64(params.get_or("seed", DEFAULT_SEED));
+        let constraints = seeded.gen_range(1..=constraints.max(1));
+        let public = seeded.gen_range(0..=MAX_PUBLIC);
+        let variables = public + seeded.gen_range(1..=2 * constraints);
+        let nonzeros = [(); 3].map(|_| {
+            let low = seeded.gen_range(1..=MAX_NONZEROS);
+            low..=seeded.gen_range(low..=MAX_NONZEROS)
+        });
+        (constraints, variables, public, nonzeros, seeded.next_u64())
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for RandomR1csCircuit<F> {
+    fn name() -> &'static str {
+        "random_r1cs"
+    }
+
+    fn description() -> &'static str {
+        "Random satisfiable R1CS whose shape comes from a seed"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "seed", default: "0", description: "Shape seed" },
+        ]
+    }
+
+    /// At most `--constraints` constraints and 16 public inputs, with rows of
+    /// 1 to 4 non-zeros per matrix.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (constraints, variables, public, nonzeros, shape_seed) = Self::shape(constraints, params);
+        RandomR1csCircuit(DensityCircuit::new(rng, constraints, variables, public, nonzeros, shape_seed, printing))
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (constraints, variables, public, nonzeros, shape_seed) = Self::shape(constraints, params);
+        RandomR1csCircuit(DensityCircuit::blank(constraints, variables, public, nonzeros, shape_seed, printing))
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let (constraints, variables, public, nonzeros, shape_seed) = Self::shape(constraints, params);
+        let free = witness.fields("free")?;
+        expect_len("free", &free, DensityCircuit::<F>::num_free(constraints, variables, public))?;
+        Ok(RandomR1csCircuit(DensityCircuit::from_values(free, constraints, variables, public, nonzeros, shape_seed, printing)))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = self.0.witness();
+        witness.circuit = Self::name().to_string();
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        RandomR1csCircuit(self.0.into_blank())
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        self.0.effective_size()
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for RandomR1csCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        self.0.generate_constraints(cs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as BlsFr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn random_shapes_are_satisfied() {
+        let rng = &mut ark_std::test_rng();
+        for seed in 0..300u64 {
+            let mut params = CircuitParams::default();
+            params.insert("seed", &seed.to_string());
+            let (constraints, _, public, _, _) = RandomR1csCircuit::<BlsFr>::shape(64, &params);
+            let circuit = RandomR1csCircuit::<BlsFr>::new_random(rng, 64, &params, false);
+
+            let cs = ConstraintSystem::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap(), "seed {}: {:?}", seed, cs.which_is_unsatisfied().unwrap());
+            assert_eq!(cs.num_constraints(), constraints, "seed {}", seed);
+            assert_eq!(cs.num_instance_variables() - 1, public, "seed {}", seed);
+        }
+    }
+}
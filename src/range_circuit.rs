@@ -0,0 +1,207 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::{expect_len, invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+const DEFAULT_BITS: usize = 32;
+const MAX_BITS: usize = 128;
+
+/// Proves, for a batch of pairs, that the witness `x` and the public `y` lie in
+/// `[0, 2^k)` and that `x < y`. Each range check decomposes the value into k
+/// boolean bits, and the comparison is a range check on `y - x - 1`, which
+/// wraps around the field when `x >= y`.
+#[derive(Clone)]
+pub struct RangeCircuit<F: Field> {
+    pairs: Vec<(Option<u128>, Option<u128>)>,
+    bits: usize,
+    print: bool,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> RangeCircuit<F> {
+    /// `pairs` holds `(x, y)`. Pairs outside `[0, 2^bits)` or with `x >= y`
+    /// give an unsatisfied circuit.
+    pub fn new(pairs: Vec<(u128, u128)>, bits: usize, printing: bool) -> Self {
+        let batch = pairs.len();
+        RangeCircuit {
+            pairs: pairs.into_iter().map(|(x, y)| (Some(x), Some(y))).collect(),
+            ..Self::blank(batch, bits, printing)
+        }
+    }
+
+    /// Circuit of the same shape as `new` for `batch` pairs, with no values.
+    pub fn blank(batch: usize, bits: usize, printing: bool) -> Self {
+        assert!(batch > 0, "at least one pair");
+        RangeCircuit {
+            pairs: vec![(None, None); batch],
+            bits: bits.clamp(1, MAX_BITS),
+            print: printing,
+            _field: std::marker::PhantomData,
+        }
+    }
+
+    /// Bit width and number of pairs, as described in `new_random`.
+    fn shape(constraints: usize, params: &CircuitParams) -> (usize, usize) {
+        let bits = params.get_or("bits", DEFAULT_BITS).clamp(1, MAX_BITS);
+        (bits, params.get_or("batch", constraints / (3 * bits + 3)).max(1))
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for RangeCircuit<F> {
+    fn name() -> &'static str {
+        "range"
+    }
+
+    fn description() -> &'static str {
+        "Range checks and x < y comparisons through bit decomposition"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "bits", default: "32", description: "Bit width k, at most 128" },
+            ParamSpec { key: "batch", default: "fits --constraints", description: "Number of (x, y) pairs" },
+        ]
+    }
+
+    /// `--params bits=k,batch=n` with `k <= 128`. By default as many pairs as
+    /// fit in `--constraints`, with 3k + 3 constraints per pair.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (bits, batch) = Self::shape(constraints, params);
+        let mask = if bits == MAX_BITS { u128::MAX } else { (1 << bits) - 1 };
+        let mut random = || ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) & mask;
+        let pairs = (0..batch)
+            .map(|_| {
+                let y = random().max(1);
+                (random() % y, y)
+            })
+            .collect();
+        Self::new(pairs, bits, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (bits, batch) = Self::shape(constraints, params);
+        Self::blank(batch, bits, printing)
+    }
+
+    /// The number of pairs replaces `batch`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let x: Vec<u128> = witness.integers("x")?;
+        let y: Vec<u128> = witness.integers("y")?;
+        if x.is_empty() {
+            return Err(invalid("`x` is empty"));
+        }
+        expect_len("y", &y, x.len())?;
+        Ok(Self::new(x.into_iter().zip(y).collect(), params.get_or("bits", DEFAULT_BITS), printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let (x, y): (Vec<u128>, Vec<u128>) = self
+            .pairs
+            .iter()
+            .map(|(x, y)| x.zip(*y).expect("no value for `x` and `y` to export"))
+            .unzip();
+        let mut witness = Witness::new(Self::name());
+        witness.set_integers("x", &x);
+        witness.set_integers("y", &y);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        RangeCircuit { pairs: vec![(None, None); self.pairs.len()], ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("bits", self.bits), ("batch", self.pairs.len())]
+    }
+}
+
+/// Allocates the `bits` low bits of `value` as boolean witnesses and returns
+/// their packing `sum 2^i b_i`.
+pub fn decompose<F: Field>(
+    cs: &ConstraintSystemRef<F>,
+    value: Option<u128>,
+    bits: usize,
+) -> Result<LinearCombination<F>, SynthesisError> {
+    let mut packed = lc!();
+    let mut power = F::one();
+    for i in 0..bits {
+        let b = cs.new_witness_variable(|| {
+            value.map(|v| F::from((v >> i) & 1)).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        cs.enforce_constraint(lc!() + b, lc!() + Variable::One - b, lc!())?;
+        packed += (power, b);
+        power.double_in_place();
+    }
+    Ok(packed)
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for RangeCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        for (x_val, y_val) in &self.pairs {
+            let x = cs.new_witness_variable(|| x_val.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing))?;
+            let y = cs.new_input_variable(|| y_val.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing))?;
+
+            let x_bits = decompose(&cs, *x_val, self.bits)?;
+            cs.enforce_constraint(x_bits, lc!() + Variable::One, lc!() + x)?;
+            let y_bits = decompose(&cs, *y_val, self.bits)?;
+            cs.enforce_constraint(y_bits, lc!() + Variable::One, lc!() + y)?;
+
+            let gap_val = x_val.zip(*y_val).map(|(x, y)| y.wrapping_sub(x).wrapping_sub(1));
+            let gap_bits = decompose(&cs, gap_val, self.bits)?;
+            cs.enforce_constraint(gap_bits, lc!() + Variable::One, lc!() + y - x - Variable::One)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit(pairs: Vec<(u128, u128)>) -> RangeCircuit<BlsFr> {
+        RangeCircuit::new(pairs, 8, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert!(is_satisfied(circuit(vec![(3, 10), (0, 255), (254, 255)])));
+    }
+
+    #[test]
+    fn rejects_x_not_below_y() {
+        assert!(!is_satisfied(circuit(vec![(3, 10), (5, 5)])));
+        assert!(!is_satisfied(circuit(vec![(7, 3)])));
+    }
+
+    #[test]
+    fn rejects_out_of_range() {
+        assert!(!is_satisfied(circuit(vec![(3, 256)])));
+        assert!(!is_satisfied(circuit(vec![(300, 400)])));
+    }
+
+    #[test]
+    fn rejects_wrong_bit() {
+        // Witnesses are `x`, then the 8 bits of `x`, of `y` and of the gap.
+        for index in [1, 8, 9, 17, 24] {
+            assert!(!is_satisfied_after(circuit(vec![(3, 10)]), |cs| {
+                cs.witness_assignment[index] = BlsFr::from(1u64) - cs.witness_assignment[index]
+            }));
+        }
+        assert!(!is_satisfied_after(circuit(vec![(3, 10)]), |cs| cs.witness_assignment[17] = BlsFr::from(2u64)));
+    }
+}
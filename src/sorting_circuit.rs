@@ -0,0 +1,246 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::range_circuit::decompose;
+use super::witness::{invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+const DEFAULT_BITS: usize = 32;
+const MAX_BITS: usize = 64;
+
+/// Comparators `(i, j)`, with `i < j`, of Batcher's odd-even merge sort for
+/// `length` elements. The network is built for the next power of two and the
+/// comparators that touch the padding are dropped, which is the same as
+/// padding with values larger than any input.
+pub fn odd_even_merge_sort(length: usize) -> Vec<(usize, usize)> {
+    let padded = length.next_power_of_two();
+    let mut comparators = Vec::new();
+    let mut p = 1;
+    while p < padded {
+        let mut k = p;
+        while k >= 1 {
+            let mut j = k % p;
+            while j + k < padded {
+                for i in 0..k.min(padded - j - k) {
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) && i + j + k < length {
+                        comparators.push((i + j, i + j + k));
+                    }
+                }
+                j += 2 * k;
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+    comparators
+}
+
+/// Proves that the public output is the private input array sorted in
+/// ascending order, running it through Batcher's odd-even merge sort. Inputs
+/// are range-checked to k bits, and each compare-and-swap proves its swap bit
+/// with a k-bit decomposition of `a - b - 1` (swap) or `b - a` (keep).
+#[derive(Clone)]
+pub struct SortingCircuit<F: Field> {
+    input: Vec<Option<u64>>,
+    bits: usize,
+    print: bool,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> SortingCircuit<F> {
+    /// Inputs must fit in `bits` bits for the circuit to be satisfied.
+    pub fn new(input: Vec<u64>, bits: usize, printing: bool) -> Self {
+        let length = input.len();
+        SortingCircuit { input: input.into_iter().map(Some).collect(), ..Self::blank(length, bits, printing) }
+    }
+
+    /// Circuit of the same shape as `new` for `length` inputs, with no values.
+    pub fn blank(length: usize, bits: usize, printing: bool) -> Self {
+        assert!(length > 0, "at least one input");
+        SortingCircuit {
+            input: vec![None; length],
+            bits: bits.clamp(1, MAX_BITS),
+            print: printing,
+            _field: std::marker::PhantomData,
+        }
+    }
+
+    /// Bit width and array length, as described in `new_random`.
+    fn shape(constraints: usize, params: &CircuitParams) -> (usize, usize) {
+        let bits = params.get_or("bits", DEFAULT_BITS).clamp(1, MAX_BITS);
+        let (mut shortest, mut longest) = (2, constraints.max(2));
+        while shortest < longest {
+            let middle = (shortest + longest).div_ceil(2);
+            if Self::cost(middle, bits) <= constraints { shortest = middle } else { longest = middle - 1 }
+        }
+        (bits, params.get_or("length", shortest).max(1))
+    }
+
+    fn cost(length: usize, bits: usize) -> usize {
+        length * (bits + 2) + odd_even_merge_sort(length).len() * (bits + 4)
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for SortingCircuit<F> {
+    fn name() -> &'static str {
+        "sorting"
+    }
+
+    fn description() -> &'static str {
+        "Batcher odd-even merge sort of a witness array"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "length", default: "fits --constraints", description: "Array length" },
+            ParamSpec { key: "bits", default: "32", description: "Bit width of the values, at most 64" },
+        ]
+    }
+
+    /// `--params length=n,bits=k` with `k <= 64`. By default the longest array
+    /// that fits in `--constraints`.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (bits, length) = Self::shape(constraints, params);
+        let mask = if bits == MAX_BITS { u64::MAX } else { (1 << bits) - 1 };
+        Self::new((0..length).map(|_| rng.next_u64() & mask).collect(), bits, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (bits, length) = Self::shape(constraints, params);
+        Self::blank(length, bits, printing)
+    }
+
+    /// The number of inputs replaces `length`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let input: Vec<u64> = witness.integers("input")?;
+        if input.is_empty() {
+            return Err(invalid("`input` is empty"));
+        }
+        Ok(Self::new(input, params.get_or("bits", DEFAULT_BITS), printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let input: Vec<u64> = self.input.iter().map(|x| x.expect("no value for `input` to export")).collect();
+        let mut witness = Witness::new(Self::name());
+        witness.set_integers("input", &input);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        SortingCircuit { input: vec![None; self.input.len()], ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("length", self.input.len()), ("bits", self.bits)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for SortingCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let mut wires = Vec::with_capacity(self.input.len());
+        for value in &self.input {
+            let x = cs.new_witness_variable(|| value.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing))?;
+            let packed = decompose(&cs, value.map(u128::from), self.bits)?;
+            cs.enforce_constraint(packed, lc!() + Variable::One, lc!() + x)?;
+            wires.push((x, *value));
+        }
+
+        for (i, j) in odd_even_merge_sort(self.input.len()) {
+            let ((a, a_val), (b, b_val)) = (wires[i], wires[j]);
+            let swap_val = a_val.zip(b_val).map(|(a, b)| a > b);
+            let swap = cs.new_witness_variable(|| {
+                swap_val.map(|s| ConstraintF::from(s as u64)).ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            cs.enforce_constraint(lc!() + swap, lc!() + Variable::One - swap, lc!())?;
+
+            // gap = swap * (2a - 2b - 1) + b - a, which is a - b - 1 or b - a.
+            let gap_val = a_val.zip(b_val).map(|(a, b)| if a > b { a - b - 1 } else { b - a });
+            let gap = decompose(&cs, gap_val.map(u128::from), self.bits)?;
+            let two = ConstraintF::from(2u64);
+            cs.enforce_constraint(
+                lc!() + swap,
+                lc!() + (two, a) - (two, b) - Variable::One,
+                gap - b + a,
+            )?;
+
+            let low_val = a_val.zip(b_val).map(|(a, b)| a.min(b));
+            let high_val = a_val.zip(b_val).map(|(a, b)| a.max(b));
+            let low = cs.new_witness_variable(|| low_val.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing))?;
+            let high = cs.new_witness_variable(|| high_val.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(lc!() + swap, lc!() + b - a, lc!() + low - a)?;
+            cs.enforce_constraint(lc!() + a + b - low, lc!() + Variable::One, lc!() + high)?;
+            wires[i] = (low, low_val);
+            wires[j] = (high, high_val);
+        }
+
+        for (x, value) in wires {
+            let output = cs.new_input_variable(|| value.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(lc!() + x, lc!() + Variable::One, lc!() + output)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    const BITS: usize = 8;
+
+    fn circuit() -> SortingCircuit<BlsFr> {
+        SortingCircuit::new(vec![5, 3, 9, 1, 7, 3], BITS, false)
+    }
+
+    #[test]
+    fn network_sorts() {
+        for length in 1..=17 {
+            let mut values: Vec<usize> = (0..length).rev().collect();
+            for (i, j) in odd_even_merge_sort(length) {
+                if values[i] > values[j] {
+                    values.swap(i, j);
+                }
+            }
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "length {}", length);
+        }
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(public_inputs(circuit()).unwrap(), [1u64, 3, 3, 5, 7, 9].map(BlsFr::from).to_vec());
+        assert!(is_satisfied(circuit()));
+    }
+
+    #[test]
+    fn rejects_wrong_order() {
+        assert!(!is_satisfied_after(circuit(), |cs| cs.instance_assignment.swap(1, 4)));
+    }
+
+    #[test]
+    fn rejects_flipped_swap() {
+        // Each input takes its own witness and BITS bits, then the first
+        // comparator allocates its swap bit.
+        let swap = circuit().input.len() * (1 + BITS);
+        assert!(!is_satisfied_after(circuit(), |cs| {
+            cs.witness_assignment[swap] = BlsFr::from(1u64) - cs.witness_assignment[swap]
+        }));
+    }
+
+    #[test]
+    fn rejects_out_of_range_input() {
+        assert!(!is_satisfied(SortingCircuit::<BlsFr>::new(vec![5, 256], BITS, false)));
+    }
+}
@@ -0,0 +1,174 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, seq::SliceRandom, RngCore, SeedableRng};
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::witness::{invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+const DEFAULT_NONZEROS: usize = 3;
+const MATRIX_SEED: u64 = 0x53704d56;
+
+/// Sparse matrix with `nonzeros` entries per row, as `(column, value)` pairs.
+/// Positions and values come from a fixed seed, so they only depend on the
+/// dimensions.
+pub fn sparse_matrix<F: Field>(rows: usize, columns: usize, nonzeros: usize) -> Vec<Vec<(usize, F)>> {
+    let mut rng = StdRng::seed_from_u64(MATRIX_SEED);
+    let mut all_columns: Vec<usize> = (0..columns).collect();
+    (0..rows)
+        .map(|_| {
+            let (chosen, _) = all_columns.partial_shuffle(&mut rng, nonzeros);
+            let mut row: Vec<usize> = chosen.to_vec();
+            row.sort_unstable();
+            row.into_iter().map(|column| (column, F::rand(&mut rng))).collect()
+        })
+        .collect()
+}
+
+/// Proves `y = M x` for a private `x` and a public `y`, with the sparse matrix
+/// `M` embedded as coefficients: row i is the single constraint
+/// `(sum M_ij x_j) * 1 = y_i`, so the matrix A gets `rows * nonzeros` non-zero
+/// entries regardless of the number of constraints.
+#[derive(Clone)]
+pub struct SpmvCircuit<F: Field> {
+    x: Vec<Option<F>>,
+    rows: usize,
+    nonzeros: usize,
+    print: bool,
+}
+
+impl<F: Field> SpmvCircuit<F> {
+    /// The number of columns is the length of `x`.
+    pub fn new(x: Vec<F>, rows: usize, nonzeros: usize, printing: bool) -> Self {
+        let columns = x.len();
+        SpmvCircuit { x: x.into_iter().map(Some).collect(), ..Self::blank(rows, columns, nonzeros, printing) }
+    }
+
+    /// Circuit of the same shape as `new` for `columns` columns, with no `x`.
+    pub fn blank(rows: usize, columns: usize, nonzeros: usize, printing: bool) -> Self {
+        assert!(columns > 0, "x must not be empty");
+        SpmvCircuit { x: vec![None; columns], rows: rows.max(1), nonzeros: nonzeros.clamp(1, columns), print: printing }
+    }
+
+    fn matrix(&self) -> Vec<Vec<(usize, F)>> {
+        sparse_matrix(self.rows, self.x.len(), self.nonzeros)
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for SpmvCircuit<F> {
+    fn name() -> &'static str {
+        "spmv"
+    }
+
+    fn description() -> &'static str {
+        "Sparse matrix-vector product with the matrix as coefficients"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "rows", default: "constraints", description: "Matrix rows" },
+            ParamSpec { key: "columns", default: "rows", description: "Matrix columns" },
+            ParamSpec { key: "nonzeros", default: "3", description: "Non-zeros per row" },
+        ]
+    }
+
+    /// `--params rows=m,columns=n,nonzeros=k`, by default `m = --constraints`,
+    /// `n = m` and `k = 3`, with `k <= n`.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let rows = params.get_or("rows", constraints).max(1);
+        let columns = params.get_or("columns", rows).max(1);
+        let x = (0..columns).map(|_| <F>::rand(rng)).collect();
+        Self::new(x, rows, params.get_or("nonzeros", DEFAULT_NONZEROS), printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let rows = params.get_or("rows", constraints).max(1);
+        let columns = params.get_or("columns", rows).max(1);
+        Self::blank(rows, columns, params.get_or("nonzeros", DEFAULT_NONZEROS), printing)
+    }
+
+    /// The length of `x` replaces `columns`.
+    fn from_witness(witness: &Witness, constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let x = witness.fields("x")?;
+        if x.is_empty() {
+            return Err(invalid("`x` is empty"));
+        }
+        let rows = params.get_or("rows", constraints);
+        Ok(Self::new(x, rows, params.get_or("nonzeros", DEFAULT_NONZEROS), printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("x", self.x.iter().copied());
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        SpmvCircuit { x: vec![None; self.x.len()], ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("rows", self.rows), ("columns", self.x.len()), ("nonzeros", self.nonzeros)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for SpmvCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let x = self
+            .x
+            .iter()
+            .map(|value| cs.new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in self.matrix() {
+            let y_val = row.iter().try_fold(ConstraintF::zero(), |acc, (j, m)| Some(acc + *m * self.x[*j]?));
+            let y = cs.new_input_variable(|| y_val.ok_or(SynthesisError::AssignmentMissing))?;
+            let product = row.iter().fold(LinearCombination::zero(), |acc, (j, m)| acc + (*m, x[*j]));
+            cs.enforce_constraint(product, lc!() + Variable::One, lc!() + y)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit() -> SpmvCircuit<BlsFr> {
+        SpmvCircuit::new((1..=6u64).map(BlsFr::from).collect(), 4, 2, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        let circuit = circuit();
+        let x: Vec<BlsFr> = (1..=6u64).map(BlsFr::from).collect();
+        let y: Vec<BlsFr> = circuit.matrix().iter().map(|row| row.iter().map(|(j, m)| *m * x[*j]).sum()).collect();
+        assert!(circuit.matrix().iter().all(|row| row.len() == 2));
+        assert_eq!(public_inputs(circuit.clone()).unwrap(), y);
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn rejects_wrong_output() {
+        assert!(!is_satisfied_after(circuit(), |cs| cs.instance_assignment[2] += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_wrong_x() {
+        // The witnesses are the entries of `x`.
+        let column = circuit().matrix()[0][0].0;
+        assert!(!is_satisfied_after(circuit(), |cs| cs.witness_assignment[column] += BlsFr::from(1u64)));
+    }
+}
@@ -0,0 +1,119 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit};
+use super::witness::Witness;
+use ark_ff::{Field, PrimeField};
+
+/// Proves knowledge of `x` such that `x^(2^n)` is the public input, squaring
+/// once per constraint so each one consumes the output of the previous one.
+#[derive(Copy, Clone)]
+pub struct SquaringCircuit<F: Field> {
+    x: Option<F>,
+    squarings: usize,
+    print: bool,
+}
+
+impl<F: Field> SquaringCircuit<F> {
+    pub fn new(x: F, squarings: usize, printing: bool) -> Self {
+        SquaringCircuit { x: Some(x), ..Self::blank(squarings, printing) }
+    }
+
+    /// Circuit of the same shape as `new` with no `x`.
+    pub fn blank(squarings: usize, printing: bool) -> Self {
+        SquaringCircuit { x: None, squarings: squarings.max(1), print: printing }
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for SquaringCircuit<F> {
+    fn name() -> &'static str {
+        "squaring"
+    }
+
+    fn description() -> &'static str {
+        "Iterated squaring x^(2^n), one squaring per constraint"
+    }
+
+    /// n is `--constraints`.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, _params: &CircuitParams, printing: bool) -> Self {
+        Self::new(<F>::rand(rng), constraints, printing)
+    }
+
+    fn new_blank(constraints: usize, _params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(constraints, printing)
+    }
+
+    fn from_witness(witness: &Witness, constraints: usize, _params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        Ok(Self::new(witness.field("x")?, constraints, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("x", [self.x]);
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        SquaringCircuit { x: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("squarings", self.squarings)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for SquaringCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let mut y = cs.new_witness_variable(|| self.x.ok_or(SynthesisError::AssignmentMissing))?;
+        let mut y_val = self.x;
+
+        for step in 0..self.squarings {
+            let next_val = y_val.map(|y| y.square());
+            let next = if step + 1 == self.squarings {
+                cs.new_input_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.new_witness_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce_constraint(lc!() + y, lc!() + y, lc!() + next)?;
+            y = next;
+            y_val = next_val;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit() -> SquaringCircuit<BlsFr> {
+        SquaringCircuit::new(BlsFr::from(3u64), 3, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(public_inputs(circuit()).unwrap(), vec![BlsFr::from(6561u64)]);
+        assert!(is_satisfied(circuit()));
+    }
+
+    #[test]
+    fn rejects_broken_chain() {
+        // Witnesses are `x`, then the 2 intermediate squares.
+        for index in 0..3 {
+            assert!(!is_satisfied_after(circuit(), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{print_stats, BenchCircuit, ParamSpec};
+use super::range_circuit::decompose;
+use super::witness::{expect_len, invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+/// Bits of the weights and costs, which keeps every sum far below 2^64.
+const VALUE_BITS: u32 = 16;
+
+/// Bits of the capacity slack. Weights and costs have `VALUE_BITS` bits, so
+/// sums never get close to it.
+const CAPACITY_BITS: usize = 64;
+
+/// Proves knowledge of a boolean selection vector `s` over the public weights
+/// such that `sum s_i w_i` equals the public target. With
+/// `--params capacity=true` every item also has a public cost, and
+/// `capacity - sum s_i c_i` must fit in 64 bits, which makes it a knapsack.
+/// Public inputs are the weights, the target, and then the costs and the
+/// capacity when enabled.
+#[derive(Clone)]
+pub struct SubsetSumCircuit<F: Field> {
+    weights: Vec<u64>,
+    costs: Option<Vec<u64>>,
+    capacity: u64,
+    selection: Option<Vec<bool>>,
+    print: bool,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> SubsetSumCircuit<F> {
+    /// `knapsack` holds the costs and the capacity, when enabled. Weights and
+    /// costs must fit in `VALUE_BITS` bits.
+    pub fn new(weights: Vec<u64>, selection: Vec<bool>, knapsack: Option<(Vec<u64>, u64)>, printing: bool) -> Self {
+        assert!(!weights.is_empty() && selection.len() == weights.len(), "one selection bit per weight");
+        assert!(check_bits("weights", &weights).is_ok(), "weights must fit in {} bits", VALUE_BITS);
+        let (costs, capacity) = match knapsack {
+            Some((costs, capacity)) => {
+                assert_eq!(costs.len(), weights.len(), "one cost per weight");
+                assert!(check_bits("costs", &costs).is_ok(), "costs must fit in {} bits", VALUE_BITS);
+                (Some(costs), capacity)
+            }
+            None => (None, 0),
+        };
+        SubsetSumCircuit {
+            weights,
+            costs,
+            capacity,
+            selection: Some(selection),
+            print: printing,
+            _field: std::marker::PhantomData,
+        }
+    }
+
+    /// Circuit of the same shape as `new` for `items` items. Weights, costs
+    /// and capacity are only values of the instance, so they are left at zero,
+    /// and the selection is unset.
+    pub fn blank(items: usize, with_capacity: bool, printing: bool) -> Self {
+        let knapsack = if with_capacity { Some((vec![0; items], 0)) } else { None };
+        SubsetSumCircuit { selection: None, ..Self::new(vec![0; items], vec![false; items], knapsack, printing) }
+    }
+
+    /// Number of items and whether the capacity is enabled, as described in
+    /// `new_random`.
+    fn shape(constraints: usize, params: &CircuitParams) -> (usize, bool) {
+        let with_capacity = params.get_or("capacity", false);
+        let default_items = if with_capacity { constraints / 3 } else { constraints / 2 };
+        (params.get_or("items", default_items).max(1), with_capacity)
+    }
+
+    fn target(&self) -> u64 {
+        match &self.selection {
+            Some(selection) => self.weights.iter().zip(selection).filter(|(_, s)| **s).map(|(w, _)| w).sum(),
+            None => 0,
+        }
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for SubsetSumCircuit<F> {
+    fn name() -> &'static str {
+        "subset_sum"
+    }
+
+    fn description() -> &'static str {
+        "Subset of public weights adding up to a public target"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "items", default: "fits --constraints", description: "Set size" },
+            ParamSpec { key: "capacity", default: "false", description: "Also bound the selected costs by a public capacity" },
+        ]
+    }
+
+    /// The set size is `--params items=n`, by default as many items as fit in
+    /// `--constraints`: 2 constraints per item, 3 with the capacity.
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (items, with_capacity) = Self::shape(constraints, params);
+        let mut random_u16 = || rng.next_u32() as u64 & ((1 << VALUE_BITS) - 1);
+        let weights: Vec<u64> = (0..items).map(|_| random_u16()).collect();
+        let selection: Vec<bool> = (0..items).map(|_| random_u16() & 1 == 1).collect();
+        let knapsack = if with_capacity {
+            let costs: Vec<u64> = (0..items).map(|_| random_u16()).collect();
+            let used: u64 = costs.iter().zip(&selection).filter(|(_, s)| **s).map(|(c, _)| c).sum();
+            Some((costs, used + random_u16()))
+        } else {
+            None
+        };
+        Self::new(weights, selection, knapsack, printing)
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let (items, with_capacity) = Self::shape(constraints, params);
+        Self::blank(items, with_capacity, printing)
+    }
+
+    /// The number of weights replaces `items`, and `costs` with `capacity`
+    /// in the witness replace `--params capacity=true`.
+    fn from_witness(witness: &Witness, _constraints: usize, _params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let weights: Vec<u64> = witness.integers("weights")?;
+        let selection = witness.bits("selection")?;
+        if weights.is_empty() {
+            return Err(invalid("`weights` is empty"));
+        }
+        expect_len("selection", &selection, weights.len())?;
+        check_bits("weights", &weights)?;
+        let knapsack = if witness.contains("costs") {
+            let costs: Vec<u64> = witness.integers("costs")?;
+            let capacity: Vec<u64> = witness.integers("capacity")?;
+            expect_len("costs", &costs, weights.len())?;
+            check_bits("costs", &costs)?;
+            expect_len("capacity", &capacity, 1)?;
+            Some((costs, capacity[0]))
+        } else {
+            None
+        };
+        Ok(Self::new(weights, selection, knapsack, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_integers("weights", &self.weights);
+        witness.set_bits("selection", self.selection.as_ref().expect("no value for `selection` to export"));
+        if let Some(costs) = &self.costs {
+            witness.set_integers("costs", costs);
+            witness.set_integers("capacity", &[self.capacity]);
+        }
+        witness
+    }
+
+    /// Weights, costs and capacity stay, since they are public.
+    fn into_blank(self) -> Self {
+        SubsetSumCircuit { selection: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("items", self.weights.len())]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for SubsetSumCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let selected = |i: usize| self.selection.as_ref().map(|s| s[i]);
+        let public = |values: &[u64]| {
+            values
+                .iter()
+                .map(|v| cs.new_input_variable(|| Ok(ConstraintF::from(*v))))
+                .collect::<Result<Vec<_>, SynthesisError>>()
+        };
+        let weights = public(&self.weights)?;
+        let target = cs.new_input_variable(|| Ok(ConstraintF::from(self.target())))?;
+
+        let mut selection = Vec::with_capacity(self.weights.len());
+        let mut sum = LinearCombination::zero();
+        for (i, (&w, w_val)) in weights.iter().zip(&self.weights).enumerate() {
+            let s = cs.new_witness_variable(|| {
+                selected(i).map(|s| ConstraintF::from(s as u64)).ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            cs.enforce_constraint(lc!() + s, lc!() + Variable::One - s, lc!())?;
+            let product = cs.new_witness_variable(|| {
+                selected(i).map(|s| ConstraintF::from(if s { *w_val } else { 0 })).ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            cs.enforce_constraint(lc!() + s, lc!() + w, lc!() + product)?;
+            sum += (ConstraintF::one(), product);
+            selection.push(s);
+        }
+        cs.enforce_constraint(sum, lc!() + Variable::One, lc!() + target)?;
+
+        if let Some(cost_values) = &self.costs {
+            let costs = public(cost_values)?;
+            let capacity = cs.new_input_variable(|| Ok(ConstraintF::from(self.capacity)))?;
+            let mut used = LinearCombination::zero();
+            let mut used_val = Some(0);
+            for (i, (&c, c_val)) in costs.iter().zip(cost_values).enumerate() {
+                let product_val = selected(i).map(|s| if s { *c_val } else { 0 });
+                let product = cs.new_witness_variable(|| {
+                    product_val.map(ConstraintF::from).ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                cs.enforce_constraint(lc!() + selection[i], lc!() + c, lc!() + product)?;
+                used += (ConstraintF::one(), product);
+                used_val = used_val.zip(product_val).map(|(u, p)| u + p);
+            }
+            let slack_val = used_val.map(|u| (self.capacity as u128).wrapping_sub(u as u128));
+            let slack = decompose(&cs, slack_val, CAPACITY_BITS)?;
+            cs.enforce_constraint(slack, lc!() + Variable::One, lc!() + capacity - &used)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+/// Rejects values of `key` that do not fit in `VALUE_BITS` bits, so the sums
+/// of the circuit cannot overflow.
+fn check_bits(key: &str, values: &[u64]) -> io::Result<()> {
+    match values.iter().find(|v| **v >> VALUE_BITS != 0) {
+        Some(v) => Err(invalid(format!("`{}` holds {}, which does not fit in {} bits", key, v, VALUE_BITS))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after, public_inputs};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit(knapsack: Option<(Vec<u64>, u64)>) -> SubsetSumCircuit<BlsFr> {
+        SubsetSumCircuit::new(vec![3, 5, 7, 11], vec![true, false, true, true], knapsack, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(public_inputs(circuit(None)).unwrap(), [3u64, 5, 7, 11, 21].map(BlsFr::from).to_vec());
+        assert!(is_satisfied(circuit(None)));
+        assert!(is_satisfied(circuit(Some((vec![2, 2, 2, 2], 6)))));
+    }
+
+    #[test]
+    fn rejects_wrong_target() {
+        // Instance: one, the four weights, then the target.
+        assert!(!is_satisfied_after(circuit(None), |cs| cs.instance_assignment[5] += BlsFr::from(1u64)));
+    }
+
+    #[test]
+    fn rejects_non_boolean_selection() {
+        // Doubling the first selection, its product and the target keeps the
+        // product and sum constraints satisfied, so only the boolean check
+        // can catch it.
+        assert!(!is_satisfied_after(circuit(None), |cs| {
+            cs.witness_assignment[0] = BlsFr::from(2u64);
+            cs.witness_assignment[1] = BlsFr::from(6u64);
+            cs.instance_assignment[5] += BlsFr::from(3u64);
+        }));
+    }
+
+    #[test]
+    fn rejects_wrong_product_or_slack_bit() {
+        // Witnesses are `s_i, s_i * w_i` for each item, then `s_i * c_i` and
+        // the slack bits.
+        let knapsack = || circuit(Some((vec![2, 2, 2, 2], 6)));
+        assert!(!is_satisfied_after(circuit(None), |cs| cs.witness_assignment[2] = BlsFr::from(1u64)));
+        for index in [1, 8] {
+            assert!(!is_satisfied_after(knapsack(), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+        assert!(!is_satisfied_after(knapsack(), |cs| {
+            cs.witness_assignment[12] = BlsFr::from(1u64) - cs.witness_assignment[12]
+        }));
+    }
+
+    #[test]
+    fn rejects_exceeded_capacity() {
+        assert!(!is_satisfied(circuit(Some((vec![2, 2, 2, 2], 5)))));
+    }
+
+    #[test]
+    fn rejects_wide_witness_values() {
+        let build = |weights: &[u64], costs: &[u64]| {
+            let mut witness = Witness::new("subset_sum");
+            witness.set_integers("weights", weights);
+            witness.set_bits("selection", &[true, true]);
+            witness.set_integers("costs", costs);
+            witness.set_integers("capacity", &[10]);
+            SubsetSumCircuit::<BlsFr>::from_witness(&witness, 0, &CircuitParams::default(), false)
+        };
+        assert!(build(&[1, u64::MAX], &[1, 2]).is_err());
+        assert!(build(&[1, 2], &[1, 1 << 16]).is_err());
+        assert!(build(&[1, 2], &[1, 2]).is_ok());
+    }
+}
@@ -0,0 +1,262 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{or_exit, print_stats, BenchCircuit, ParamSpec};
+use super::witness::{expect_len, invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+const DEFAULT_BOX_SIZE: usize = 3;
+
+/// Generates a solved n²×n² grid from `seed` and keeps each cell as a given
+/// with probability 1/2. Both are row-major with values in `1..=n²`, and a
+/// blank is 0 in the givens.
+pub fn generate_puzzle(n: usize, seed: u64) -> (Vec<usize>, Vec<usize>) {
+    let size = n * n;
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Rows and columns are shuffled within their band and bands among
+    // themselves, which keeps every box valid.
+    let shuffled_lines = |rng: &mut StdRng| -> Vec<usize> {
+        let mut bands: Vec<usize> = (0..n).collect();
+        bands.shuffle(rng);
+        bands
+            .into_iter()
+            .flat_map(|band| {
+                let mut lines: Vec<usize> = (0..n).map(|line| band * n + line).collect();
+                lines.shuffle(rng);
+                lines
+            })
+            .collect()
+    };
+    let rows = shuffled_lines(&mut rng);
+    let columns = shuffled_lines(&mut rng);
+    let mut digits: Vec<usize> = (1..=size).collect();
+    digits.shuffle(&mut rng);
+
+    let mut solution = Vec::with_capacity(size * size);
+    for r in &rows {
+        for c in &columns {
+            solution.push(digits[(n * (r % n) + r / n + c) % size]);
+        }
+    }
+    let givens = solution.iter().map(|&v| if rng.gen::<bool>() { v } else { 0 }).collect();
+    (givens, solution)
+}
+
+/// Proves knowledge of a solution to the public n²×n² puzzle. The givens are
+/// public inputs, 0 meaning blank, and `g * (s - g) = 0` ties each one to its
+/// cell. Every cell satisfies `(s - 1)(s - 2)...(s - n²) = 0`, and the product
+/// of the differences between each pair of cells sharing a row, column or box
+/// has an inverse, so every group holds a permutation of `1..=n²`.
+#[derive(Clone)]
+pub struct SudokuCircuit<F: Field> {
+    n: usize,
+    givens: Vec<usize>,
+    solution: Option<Vec<usize>>,
+    print: bool,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> SudokuCircuit<F> {
+    /// `givens` and `solution` are row-major n²×n² grids as returned by
+    /// `generate_puzzle`.
+    pub fn new(n: usize, givens: Vec<usize>, solution: Vec<usize>, printing: bool) -> Self {
+        let cells = n * n * n * n;
+        assert!(n >= 2 && givens.len() == cells && solution.len() == cells, "grids must have n^4 cells");
+        SudokuCircuit {
+            n,
+            givens,
+            solution: Some(solution),
+            print: printing,
+            _field: std::marker::PhantomData,
+        }
+    }
+
+    /// Circuit of the same shape as `new`. The givens are only values of the
+    /// instance, so they are left blank, and the solution is unset.
+    pub fn blank(n: usize, printing: bool) -> Self {
+        let cells = n * n * n * n;
+        SudokuCircuit { solution: None, ..Self::new(n, vec![0; cells], vec![0; cells], printing) }
+    }
+
+    /// Cell indices of every row, column and box.
+    fn groups(&self) -> Vec<Vec<usize>> {
+        let n = self.n;
+        let size = n * n;
+        let mut groups = Vec::with_capacity(3 * size);
+        for i in 0..size {
+            groups.push((0..size).map(|j| i * size + j).collect());
+            groups.push((0..size).map(|j| j * size + i).collect());
+            let (top, left) = (n * (i / n), n * (i % n));
+            groups.push((0..size).map(|j| (top + j / n) * size + left + j % n).collect());
+        }
+        groups
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for SudokuCircuit<F> {
+    fn name() -> &'static str {
+        "sudoku"
+    }
+
+    fn description() -> &'static str {
+        "Solution of an n²×n² Sudoku with public givens"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "n", default: "3", description: "Box size" },
+            ParamSpec { key: "seed", default: "random", description: "Puzzle generator seed" },
+        ]
+    }
+
+    /// `--params n=3,seed=s` gives a 9×9 puzzle. The seed is random by default
+    /// and `--constraints` is ignored.
+    fn new_random<R: RngCore>(rng: &mut R, _constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let n = params.get_or("n", DEFAULT_BOX_SIZE).max(2);
+        let seed = or_exit(params.get_parsed("seed"), "invalid --params").unwrap_or_else(|| rng.next_u64());
+        let (givens, solution) = generate_puzzle(n, seed);
+        Self::new(n, givens, solution, printing)
+    }
+
+    fn new_blank(_constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(params.get_or("n", DEFAULT_BOX_SIZE).max(2), printing)
+    }
+
+    /// The number of cells replaces `n`.
+    fn from_witness(witness: &Witness, _constraints: usize, _params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let givens: Vec<usize> = witness.integers("givens")?;
+        let solution: Vec<usize> = witness.integers("solution")?;
+        let n = (2usize..).find(|n| n.pow(4) >= givens.len()).unwrap();
+        if n.pow(4) != givens.len() {
+            return Err(invalid(format!("`givens` has {} cells, which is not n^4 for any n >= 2", givens.len())));
+        }
+        expect_len("solution", &solution, givens.len())?;
+        Ok(Self::new(n, givens, solution, printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_integers("givens", &self.givens);
+        witness.set_integers("solution", self.solution.as_ref().expect("no value for `solution` to export"));
+        witness
+    }
+
+    /// The givens stay, since they are public.
+    fn into_blank(self) -> Self {
+        SudokuCircuit { solution: None, ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("n", self.n)]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for SudokuCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let size = self.n * self.n;
+        let value = |cell: usize| {
+            self.solution.as_ref().map(|s| ConstraintF::from(s[cell] as u64)).ok_or(SynthesisError::AssignmentMissing)
+        };
+
+        let givens = self
+            .givens
+            .iter()
+            .map(|&g| cs.new_input_variable(|| Ok(ConstraintF::from(g as u64))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let cells = (0..size * size)
+            .map(|cell| cs.new_witness_variable(|| value(cell)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (cell, (&g, &s)) in givens.iter().zip(&cells).enumerate() {
+            cs.enforce_constraint(lc!() + g, lc!() + s - g, lc!())?;
+
+            let mut product: LinearCombination<ConstraintF> = lc!() + s - Variable::One;
+            let mut product_val = value(cell).ok().map(|s| s - ConstraintF::one());
+            for k in 2..size {
+                let k = ConstraintF::from(k as u64);
+                let factor = lc!() + s - (k, Variable::One);
+                let next_val = product_val.zip(value(cell).ok()).map(|(p, s)| p * (s - k));
+                let next = cs.new_witness_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?;
+                cs.enforce_constraint(product, factor, lc!() + next)?;
+                product = lc!() + next;
+                product_val = next_val;
+            }
+            let last = ConstraintF::from(size as u64);
+            cs.enforce_constraint(product, lc!() + s - (last, Variable::One), lc!())?;
+        }
+
+        let mut product: LinearCombination<ConstraintF> = lc!() + Variable::One;
+        let mut product_val = Some(ConstraintF::one());
+        for group in self.groups() {
+            for (i, &a) in group.iter().enumerate() {
+                for &b in &group[(i + 1)..] {
+                    let next_val = product_val.zip(value(a).ok()).zip(value(b).ok()).map(|((p, a), b)| p * (a - b));
+                    let next = cs.new_witness_variable(|| next_val.ok_or(SynthesisError::AssignmentMissing))?;
+                    cs.enforce_constraint(product, lc!() + cells[a] - cells[b], lc!() + next)?;
+                    product = lc!() + next;
+                    product_val = next_val;
+                }
+            }
+        }
+        let inverse = cs.new_witness_variable(|| {
+            product_val.and_then(|p| p.inverse()).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        cs.enforce_constraint(product, lc!() + inverse, lc!() + Variable::One)?;
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit(n: usize) -> SudokuCircuit<BlsFr> {
+        let (givens, solution) = generate_puzzle(n, 7);
+        SudokuCircuit::new(n, givens, solution, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert!(is_satisfied(circuit(2)));
+        assert!(is_satisfied(circuit(3)));
+    }
+
+    #[test]
+    fn rejects_bad_cell() {
+        // The 16 cells are the first witnesses, row by row.
+        let circuit = circuit(2);
+        let solution = circuit.solution.clone().unwrap();
+        assert!(!is_satisfied_after(circuit.clone(), |cs| cs.witness_assignment.swap(0, 1)));
+        assert!(!is_satisfied_after(circuit.clone(), |cs| cs.witness_assignment[0] = BlsFr::from(5u64)));
+
+        // A solution that ignores a given.
+        let cell = circuit.givens.iter().position(|&g| g != 0).unwrap();
+        let mut givens = circuit.givens.clone();
+        givens[cell] = givens[cell] % 4 + 1;
+        assert!(!is_satisfied(SudokuCircuit::<BlsFr>::new(2, givens, solution, false)));
+    }
+
+    #[test]
+    fn from_witness_rejects_bad_grids() {
+        let params = CircuitParams::default();
+        for cells in [0, 3, 17] {
+            let mut witness = Witness::new("sudoku");
+            witness.set_integers("givens", &vec![0usize; cells]);
+            witness.set_integers("solution", &vec![1usize; cells]);
+            assert!(SudokuCircuit::<BlsFr>::from_witness(&witness, 0, &params, false).is_err());
+        }
+    }
+}
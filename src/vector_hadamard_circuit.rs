@@ -0,0 +1,168 @@
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_std::rand::RngCore;
+use std::io;
+
+use super::circuit_params::CircuitParams;
+use super::circuit_traits::{digest, digest_lc, print_stats, BenchCircuit, ParamSpec};
+use super::witness::{expect_len, invalid, Witness};
+use ark_ff::{Field, PrimeField};
+
+/// Element-wise product `c = a ∘ b` of two witness vectors of length
+/// `--constraints`. With `--params digest=true` only the digest of `c` is
+/// public, otherwise every `c_i` is a public input.
+#[derive(Clone)]
+pub struct VectorHadamardCircuit<F: Field> {
+    a: Vec<Option<F>>,
+    b: Vec<Option<F>>,
+    digest: bool,
+    print: bool,
+}
+
+impl<F: Field> VectorHadamardCircuit<F> {
+    pub fn new(a: Vec<F>, b: Vec<F>, digest: bool, printing: bool) -> Self {
+        assert!(!a.is_empty() && a.len() == b.len(), "vectors must be non-empty and of the same length");
+        VectorHadamardCircuit {
+            a: a.into_iter().map(Some).collect(),
+            b: b.into_iter().map(Some).collect(),
+            digest,
+            print: printing,
+        }
+    }
+
+    /// Circuit of the same shape as `new` for vectors of length `n`, with no
+    /// entries.
+    pub fn blank(n: usize, digest: bool, printing: bool) -> Self {
+        assert!(n > 0, "vectors must be non-empty");
+        VectorHadamardCircuit { a: vec![None; n], b: vec![None; n], digest, print: printing }
+    }
+
+    fn products(&self) -> Vec<Option<F>> {
+        self.a.iter().zip(&self.b).map(|(a, b)| Some((*a)? * (*b)?)).collect()
+    }
+}
+
+impl<F: PrimeField> BenchCircuit<F> for VectorHadamardCircuit<F> {
+    fn name() -> &'static str {
+        "vector_hadamard"
+    }
+
+    fn description() -> &'static str {
+        "Element-wise product of two witness vectors"
+    }
+
+    fn params() -> &'static [ParamSpec] {
+        &[
+            ParamSpec { key: "digest", default: "false", description: "Publish only the digest of the result" },
+        ]
+    }
+
+    fn new_random<R: RngCore>(rng: &mut R, constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        let n = if constraints < 1 { 1 } else { constraints };
+        Self::new(
+            (0..n).map(|_| <F>::rand(rng)).collect(),
+            (0..n).map(|_| <F>::rand(rng)).collect(),
+            params.get_or("digest", false),
+            printing,
+        )
+    }
+
+    fn new_blank(constraints: usize, params: &CircuitParams, printing: bool) -> Self {
+        Self::blank(constraints.max(1), params.get_or("digest", false), printing)
+    }
+
+    /// The length of `a` and `b` replaces `--constraints`.
+    fn from_witness(witness: &Witness, _constraints: usize, params: &CircuitParams, printing: bool) -> io::Result<Self> {
+        let a = witness.fields("a")?;
+        let b = witness.fields("b")?;
+        if a.is_empty() {
+            return Err(invalid("`a` is empty"));
+        }
+        expect_len("b", &b, a.len())?;
+        Ok(Self::new(a, b, params.get_or("digest", false), printing))
+    }
+
+    fn witness(&self) -> Witness {
+        let mut witness = Witness::new(Self::name());
+        witness.set_fields("a", self.a.iter().copied());
+        witness.set_fields("b", self.b.iter().copied());
+        witness
+    }
+
+    fn into_blank(self) -> Self {
+        VectorHadamardCircuit { a: vec![None; self.a.len()], b: vec![None; self.b.len()], ..self }
+    }
+
+    fn effective_size(&self) -> Vec<(&'static str, usize)> {
+        vec![("length", self.a.len())]
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for VectorHadamardCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let products = self.products();
+        let mut outputs = Vec::with_capacity(products.len());
+
+        for ((a_val, b_val), c_val) in self.a.iter().zip(&self.b).zip(&products) {
+            let a = cs.new_witness_variable(|| a_val.ok_or(SynthesisError::AssignmentMissing))?;
+            let b = cs.new_witness_variable(|| b_val.ok_or(SynthesisError::AssignmentMissing))?;
+            let c = if self.digest {
+                cs.new_witness_variable(|| c_val.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.new_input_variable(|| c_val.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce_constraint(lc!() + a, lc!() + b, lc!() + c)?;
+            outputs.push(c);
+        }
+
+        if self.digest {
+            let d = cs.new_input_variable(|| digest(&products).ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(digest_lc(&outputs), lc!() + Variable::One, lc!() + d)?;
+        }
+        if self.print {
+            print_stats(&cs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_traits::{is_satisfied, is_satisfied_after};
+    use ark_bls12_381::Fr as BlsFr;
+
+    fn circuit(digest: bool) -> VectorHadamardCircuit<BlsFr> {
+        let a = [2u64, 3, 5].map(BlsFr::from).to_vec();
+        let b = [7u64, 11, 13].map(BlsFr::from).to_vec();
+        VectorHadamardCircuit::new(a, b, digest, false)
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(circuit(false).get_public_inputs(), [14u64, 33, 65].map(BlsFr::from).to_vec());
+        assert!(is_satisfied(circuit(false)));
+        assert!(is_satisfied(circuit(true)));
+    }
+
+    #[test]
+    fn rejects_wrong_product() {
+        for digest in [false, true] {
+            assert!(!is_satisfied_after(circuit(digest), |cs| cs.instance_assignment[1] += BlsFr::from(1u64)));
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_witness() {
+        // Witnesses are `a_i, b_i, c_i` for each index; with a digest `c_i` is
+        // a witness too.
+        for index in [0, 1, 2, 5] {
+            assert!(!is_satisfied_after(circuit(true), |cs| cs.witness_assignment[index] += BlsFr::from(1u64)));
+        }
+    }
+}
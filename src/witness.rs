@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::str::FromStr;
+
+use ark_ff::{FpParameters, PrimeField};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+/// Explicit values a circuit is built from, as stored in a witness file:
+///
+/// ```json
+/// {"circuit": "hadamard", "values": {"a": ["12"], "b": ["34"]}}
+/// ```
+///
+/// Every value is a list of decimal strings, whether it holds field elements,
+/// integers or bits (`0` or `1`). The keys are the ones each circuit writes with
+/// `BenchCircuit::witness`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Witness {
+    pub circuit: String,
+    values: BTreeMap<String, Vec<String>>,
+}
+
+impl Witness {
+    pub fn new(circuit: &str) -> Self {
+        Witness { circuit: circuit.to_string(), values: BTreeMap::new() }
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        serde_json::from_reader(file).map_err(io::Error::from)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Stores field elements under `key`. Panics on a missing value, since only
+    /// an assigned circuit has a witness to export.
+    pub fn set_fields<F: PrimeField, I: IntoIterator<Item = Option<F>>>(&mut self, key: &str, values: I) {
+        let values = values
+            .into_iter()
+            .map(|value| {
+                let value: BigUint = value.unwrap_or_else(|| panic!("no value for `{}` to export", key)).into();
+                value.to_string()
+            })
+            .collect();
+        self.values.insert(key.to_string(), values);
+    }
+
+    pub fn set_integers<T: ToString>(&mut self, key: &str, values: &[T]) {
+        self.values.insert(key.to_string(), values.iter().map(T::to_string).collect());
+    }
+
+    pub fn set_bits(&mut self, key: &str, values: &[bool]) {
+        self.values.insert(key.to_string(), values.iter().map(|b| if *b { "1" } else { "0" }.to_string()).collect());
+    }
+
+    pub fn fields<F: PrimeField>(&self, key: &str) -> io::Result<Vec<F>> {
+        let modulus: BigUint = F::Params::MODULUS.into();
+        self.strings(key)?
+            .iter()
+            .map(|value| match BigUint::parse_bytes(value.as_bytes(), 10) {
+                Some(element) if element < modulus => Ok(F::from(element)),
+                _ => Err(invalid(format!("invalid field element `{}` in `{}`", value, key))),
+            })
+            .collect()
+    }
+
+    /// The single field element stored under `key`.
+    pub fn field<F: PrimeField>(&self, key: &str) -> io::Result<F> {
+        let values = self.fields(key)?;
+        expect_len(key, &values, 1)?;
+        Ok(values[0])
+    }
+
+    pub fn integers<T: FromStr>(&self, key: &str) -> io::Result<Vec<T>> {
+        self.strings(key)?
+            .iter()
+            .map(|value| value.parse().map_err(|_| invalid(format!("invalid integer `{}` in `{}`", value, key))))
+            .collect()
+    }
+
+    pub fn bits(&self, key: &str) -> io::Result<Vec<bool>> {
+        self.strings(key)?
+            .iter()
+            .map(|value| match value.as_str() {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(invalid(format!("invalid bit `{}` in `{}`", value, key))),
+            })
+            .collect()
+    }
+
+    fn strings(&self, key: &str) -> io::Result<&Vec<String>> {
+        self.values.get(key).ok_or_else(|| invalid(format!("missing `{}` in witness", key)))
+    }
+}
+
+/// Checks that `key` holds `len` values, for sizes fixed by other values or by
+/// `--params`.
+pub fn expect_len<T>(key: &str, values: &[T], len: usize) -> io::Result<()> {
+    if values.len() == len {
+        Ok(())
+    } else {
+        Err(invalid(format!("expected {} values in `{}`, found {}", len, key, values.len())))
+    }
+}
+
+pub fn invalid<M: Into<String>>(message: M) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}